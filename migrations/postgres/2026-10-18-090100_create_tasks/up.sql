@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS tasks (
+    task_id SERIAL PRIMARY KEY,
+    project_id INT NOT NULL REFERENCES projects (project_id),
+    task_name VARCHAR(20) NOT NULL,
+    username VARCHAR(20) NOT NULL,
+    planned_time VARCHAR(20),
+    UNIQUE (project_id, task_name)
+);
+
+-- Named like the constraint above, so that it's only added to tables created before migrations
+CREATE UNIQUE INDEX IF NOT EXISTS tasks_project_id_task_name_key ON tasks (project_id, task_name);
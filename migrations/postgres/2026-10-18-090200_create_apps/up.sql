@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS apps (
+    app_id SERIAL PRIMARY KEY,
+    app_name VARCHAR(20) NOT NULL UNIQUE
+);
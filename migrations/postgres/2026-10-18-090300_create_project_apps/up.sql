@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS project_apps (
+    id SERIAL PRIMARY KEY,
+    project_id INT NOT NULL REFERENCES projects (project_id),
+    app_id INT NOT NULL REFERENCES apps (app_id),
+    UNIQUE (project_id, app_id)
+);
+
+-- Named like the constraint above, so that it's only added to tables created before migrations
+CREATE UNIQUE INDEX IF NOT EXISTS project_apps_project_id_app_id_key ON project_apps (project_id, app_id);
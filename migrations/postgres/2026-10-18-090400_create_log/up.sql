@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS log (
+    log_id SERIAL PRIMARY KEY,
+    task_id INT NOT NULL REFERENCES tasks (task_id),
+    log_type VARCHAR(1) NOT NULL CHECK (log_type IN ('B', 'P', 'R', 'E')),
+    date TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS log_task_id_date_idx ON log (task_id, date);
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS task_apps (
+    id SERIAL PRIMARY KEY,
+    task_id INT NOT NULL REFERENCES tasks (task_id),
+    app_id INT NOT NULL REFERENCES apps (app_id),
+    UNIQUE (task_id, app_id)
+);
@@ -0,0 +1,13 @@
+ALTER TABLE projects ADD COLUMN planned_minutes INT CHECK (planned_minutes >= 0);
+UPDATE projects SET planned_minutes = split_part(planned_time, ':', 1)::INT * 1440
+    + split_part(planned_time, ':', 2)::INT * 60
+    + split_part(planned_time, ':', 3)::INT
+WHERE planned_time ~ '^\d+:\d+:\d+$';
+ALTER TABLE projects DROP COLUMN planned_time;
+
+ALTER TABLE tasks ADD COLUMN planned_minutes INT CHECK (planned_minutes >= 0);
+UPDATE tasks SET planned_minutes = split_part(planned_time, ':', 1)::INT * 1440
+    + split_part(planned_time, ':', 2)::INT * 60
+    + split_part(planned_time, ':', 3)::INT
+WHERE planned_time ~ '^\d+:\d+:\d+$';
+ALTER TABLE tasks DROP COLUMN planned_time;
@@ -0,0 +1,18 @@
+-- The clock of a running pomodoro mode, one per user, kept so that it can be resumed
+CREATE TABLE IF NOT EXISTS pomodoro_timers (
+    username VARCHAR PRIMARY KEY,
+    owner VARCHAR NOT NULL,
+    task_id INT NOT NULL REFERENCES tasks (task_id),
+    kind VARCHAR(5) NOT NULL CHECK (kind IN ('work', 'break')),
+    title VARCHAR NOT NULL,
+    started_at TIMESTAMP NOT NULL,
+    planned_seconds INT NOT NULL CHECK (planned_seconds >= 0),
+    deadline TIMESTAMP,
+    remaining_seconds INT NOT NULL CHECK (remaining_seconds >= 0),
+    round INT NOT NULL CHECK (round > 0),
+    work_seconds INT,
+    break_seconds INT,
+    long_break_seconds INT,
+    rounds INT,
+    updated_at TIMESTAMP NOT NULL
+);
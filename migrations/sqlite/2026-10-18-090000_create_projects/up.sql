@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS projects (
+    project_id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    project_name VARCHAR(20) NOT NULL UNIQUE,
+    username VARCHAR(20) NOT NULL,
+    planned_time VARCHAR(20)
+);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS tasks (
+    task_id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    project_id INTEGER NOT NULL REFERENCES projects (project_id),
+    task_name VARCHAR(20) NOT NULL,
+    username VARCHAR(20) NOT NULL,
+    planned_time VARCHAR(20),
+    UNIQUE (project_id, task_name)
+);
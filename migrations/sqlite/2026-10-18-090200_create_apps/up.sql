@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS apps (
+    app_id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    app_name VARCHAR(20) NOT NULL UNIQUE
+);
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS project_apps (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    project_id INTEGER NOT NULL REFERENCES projects (project_id),
+    app_id INTEGER NOT NULL REFERENCES apps (app_id),
+    UNIQUE (project_id, app_id)
+);
@@ -0,0 +1,2 @@
+ALTER TABLE tasks DROP COLUMN archived;
+ALTER TABLE projects DROP COLUMN archived;
@@ -0,0 +1,2 @@
+ALTER TABLE projects ADD COLUMN archived BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE tasks ADD COLUMN archived BOOLEAN NOT NULL DEFAULT 0;
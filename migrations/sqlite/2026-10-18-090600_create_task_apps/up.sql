@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS task_apps (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    task_id INTEGER NOT NULL REFERENCES tasks (task_id),
+    app_id INTEGER NOT NULL REFERENCES apps (app_id),
+    UNIQUE (task_id, app_id)
+);
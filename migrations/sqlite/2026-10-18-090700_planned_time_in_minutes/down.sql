@@ -0,0 +1,9 @@
+ALTER TABLE tasks ADD COLUMN planned_time VARCHAR(20);
+UPDATE tasks SET planned_time = (planned_minutes / 1440) || ':' || (planned_minutes % 1440 / 60)
+    || ':' || (planned_minutes % 60);
+ALTER TABLE tasks DROP COLUMN planned_minutes;
+
+ALTER TABLE projects ADD COLUMN planned_time VARCHAR(20);
+UPDATE projects SET planned_time = (planned_minutes / 1440) || ':' || (planned_minutes % 1440 / 60)
+    || ':' || (planned_minutes % 60);
+ALTER TABLE projects DROP COLUMN planned_minutes;
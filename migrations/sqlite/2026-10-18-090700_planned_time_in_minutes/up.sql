@@ -0,0 +1,20 @@
+-- planned_time has format DAYS:HOURS:MINUTES, rest is what follows the first colon
+ALTER TABLE projects ADD COLUMN planned_minutes INTEGER CHECK (planned_minutes >= 0);
+UPDATE projects SET planned_minutes = (
+    SELECT CAST(substr(planned_time, 1, instr(planned_time, ':') - 1) AS INTEGER) * 1440
+        + CAST(substr(rest, 1, instr(rest, ':') - 1) AS INTEGER) * 60
+        + CAST(substr(rest, instr(rest, ':') + 1) AS INTEGER)
+    FROM (SELECT substr(planned_time, instr(planned_time, ':') + 1) AS rest)
+)
+WHERE planned_time GLOB '[0-9]*:[0-9]*:[0-9]*';
+ALTER TABLE projects DROP COLUMN planned_time;
+
+ALTER TABLE tasks ADD COLUMN planned_minutes INTEGER CHECK (planned_minutes >= 0);
+UPDATE tasks SET planned_minutes = (
+    SELECT CAST(substr(planned_time, 1, instr(planned_time, ':') - 1) AS INTEGER) * 1440
+        + CAST(substr(rest, 1, instr(rest, ':') - 1) AS INTEGER) * 60
+        + CAST(substr(rest, instr(rest, ':') + 1) AS INTEGER)
+    FROM (SELECT substr(planned_time, instr(planned_time, ':') + 1) AS rest)
+)
+WHERE planned_time GLOB '[0-9]*:[0-9]*:[0-9]*';
+ALTER TABLE tasks DROP COLUMN planned_time;
@@ -0,0 +1 @@
+DROP TABLE pomodoro_sessions;
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS pomodoro_sessions (
+    session_id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    task_id INTEGER NOT NULL REFERENCES tasks (task_id),
+    kind VARCHAR(5) NOT NULL CHECK (kind IN ('work', 'break')),
+    started_at TIMESTAMP NOT NULL,
+    planned_seconds INTEGER NOT NULL CHECK (planned_seconds >= 0),
+    actual_seconds INTEGER NOT NULL CHECK (actual_seconds >= 0),
+    completed BOOLEAN NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS pomodoro_sessions_started_at_idx ON pomodoro_sessions (started_at);
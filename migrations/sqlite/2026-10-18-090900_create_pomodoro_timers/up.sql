@@ -0,0 +1,18 @@
+-- The clock of a running pomodoro mode, one per user, kept so that it can be resumed
+CREATE TABLE IF NOT EXISTS pomodoro_timers (
+    username VARCHAR PRIMARY KEY NOT NULL,
+    owner VARCHAR NOT NULL,
+    task_id INTEGER NOT NULL REFERENCES tasks (task_id),
+    kind VARCHAR(5) NOT NULL CHECK (kind IN ('work', 'break')),
+    title VARCHAR NOT NULL,
+    started_at TIMESTAMP NOT NULL,
+    planned_seconds INTEGER NOT NULL CHECK (planned_seconds >= 0),
+    deadline TIMESTAMP,
+    remaining_seconds INTEGER NOT NULL CHECK (remaining_seconds >= 0),
+    round INTEGER NOT NULL CHECK (round > 0),
+    work_seconds INTEGER,
+    break_seconds INTEGER,
+    long_break_seconds INTEGER,
+    rounds INTEGER,
+    updated_at TIMESTAMP NOT NULL
+);
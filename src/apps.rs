@@ -3,21 +3,27 @@ use comfy_table::{
     ContentArrangement, Table,
 };
 use std::process;
+use workflow::DbConnection;
 
 use crate::db_operations::{self, apps::get_app_stats};
+use crate::output::{self, Format};
+use crate::{projects, tasks};
+use serde::Serialize;
+use workflow::models::App;
 
-pub fn add_app(args: &[String], display_communicates: bool) {
-    if args.len() < 1 {
+pub fn add_app(args: &[String], display_communicates: bool, connection: &mut DbConnection) {
+    if args.is_empty() {
         eprintln!("Too few args");
         process::exit(-1);
     }
 
     for x in args {
-        match db_operations::apps::find_app(x) {
+        match db_operations::apps::find_app(x, connection) {
             Ok(None) => {
-                match db_operations::apps::add_app(&(x.to_lowercase()), display_communicates) {
-                    Err(x) => println!("{}", x),
-                    _ => (),
+                if let Err(x) =
+                    db_operations::apps::add_app(&(x.to_lowercase()), display_communicates, connection)
+                {
+                    println!("{}", x);
                 }
             }
             Ok(Some(_a)) => {
@@ -32,10 +38,30 @@ pub fn add_app(args: &[String], display_communicates: bool) {
     }
 }
 
-pub fn display_apps(args: &[String]) {
-    let apps = get_app_stats(args);
+/// An app with the number of projects using it.
+#[derive(Serialize)]
+struct AppUsage {
+    app_id: i32,
+    app_name: String,
+    used_in_projects: i32,
+}
+
+pub fn display_apps(format: Format, connection: &mut DbConnection) {
+    let apps = get_app_stats(&[], connection);
 
     if let Ok(x) = apps {
+        if format != Format::Table {
+            let rows: Vec<AppUsage> = x
+                .into_iter()
+                .map(|(app, used_in_projects)| AppUsage {
+                    app_id: app.app_id,
+                    app_name: app.app_name,
+                    used_in_projects: used_in_projects.unwrap_or(0),
+                })
+                .collect();
+            return output::print_rows(&rows, format);
+        }
+
         let mut table = Table::new();
         table
             .load_preset(UTF8_FULL)
@@ -62,3 +88,68 @@ pub fn display_apps(args: &[String]) {
         println!("{table}");
     }
 }
+
+pub fn manage_project_apps(
+    project: &str,
+    app_names: &[String],
+    remove: bool,
+    connection: &mut DbConnection,
+) {
+    let Some(project) = projects::resolve_project(project, connection) else {
+        return;
+    };
+    let result = if remove {
+        db_operations::apps::detach_apps_from_project(project.project_id, app_names, connection)
+            .map(|x| println!("Removed {} apps from project \"{}\"", x, project.project_name))
+    } else {
+        db_operations::apps::attach_apps_to_project(project.project_id, app_names, connection)
+            .map(|x| println!("Added {} apps to project \"{}\"", x, project.project_name))
+    };
+    if let Err(x) = result {
+        println!("{}", x);
+        return;
+    }
+
+    print_app_list(db_operations::apps::get_project_apps(
+        project.project_id,
+        connection,
+    ));
+}
+
+pub fn manage_task_apps(
+    task: &str,
+    app_names: &[String],
+    remove: bool,
+    connection: &mut DbConnection,
+) {
+    let Some(task) = tasks::resolve_task(task, connection) else {
+        return;
+    };
+    let result = if remove {
+        db_operations::apps::detach_apps_from_task(task.task_id, app_names, connection)
+            .map(|x| println!("Removed {} apps from task \"{}\"", x, task.task_name))
+    } else {
+        db_operations::apps::attach_apps_to_task(task.task_id, app_names, connection)
+            .map(|x| println!("Added {} apps to task \"{}\"", x, task.task_name))
+    };
+    if let Err(x) = result {
+        println!("{}", x);
+        return;
+    }
+
+    print_app_list(db_operations::apps::get_task_apps(task.task_id, connection));
+}
+
+fn print_app_list(apps: Result<Vec<App>, &str>) {
+    match apps {
+        Ok(x) if x.is_empty() => println!("No apps left"),
+        Ok(x) => println!(
+            "Apps: {}",
+            x.into_iter()
+                .map(|app| app.app_name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Err(x) => println!("{}", x),
+    }
+}
@@ -0,0 +1,458 @@
+use chrono::{Datelike, Days, Duration, Months, NaiveDate, NaiveDateTime, Weekday};
+use clap::{Parser, Subcommand};
+use clap_complete::Shell;
+use regex::Regex;
+
+use crate::logs;
+use crate::output::Format;
+use crate::pomodoro::Cycle;
+
+/// A project facilitating workflow control from terminal
+#[derive(Parser, Debug)]
+#[command(name = "app", version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Commands,
+
+    /// Format of listings and stats; durations in json, csv and tsv are given in seconds
+    /// [default: format in the config file, or table]
+    #[arg(long, global = true, value_enum)]
+    pub format: Option<Format>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+    /// Creates new project
+    #[command(name = "newproject")]
+    AddProject {
+        /// Name of the project
+        name: String,
+        /// Time user plans to spend on project, in format DAYS:HOURS:MINUTES or
+        /// a duration like 1d2h30m
+        #[arg(short = 't', long = "time", value_name = "TIME", value_parser = parse_planned_time)]
+        planned_time: Option<i32>,
+        /// Apps used in the project
+        #[arg(short, long, num_args = 1..)]
+        apps: Vec<String>,
+    },
+
+    /// Displays all projects
+    #[command(name = "projects")]
+    AllProjects {
+        /// Displays also archived projects
+        #[arg(short, long)]
+        all: bool,
+    },
+
+    /// Creates new task in a project given by id or name
+    #[command(name = "newtask")]
+    AddTask {
+        /// Name of the task
+        name: String,
+        /// Id or name of the project the task belongs to [default: default_project in the config
+        /// file]
+        project: Option<String>,
+        /// Time user plans to spend on task, in format DAYS:HOURS:MINUTES or
+        /// a duration like 1d2h30m
+        #[arg(short = 't', long = "time", value_name = "TIME", value_parser = parse_planned_time)]
+        planned_time: Option<i32>,
+    },
+
+    /// Displays all tasks
+    #[command(name = "tasks")]
+    AllTasks {
+        /// Displays also archived tasks and tasks of archived projects
+        #[arg(short, long)]
+        all: bool,
+    },
+
+    /// Changes name or planned time of a project
+    #[command(name = "editproject")]
+    EditProject {
+        /// Id or name of the project
+        project: String,
+        /// New name of the project
+        #[arg(short, long)]
+        name: Option<String>,
+        /// New planned time, in format DAYS:HOURS:MINUTES or
+        /// a duration like 1d2h30m
+        #[arg(short = 't', long = "time", value_name = "TIME", value_parser = parse_planned_time)]
+        planned_time: Option<i32>,
+    },
+
+    /// Changes name or planned time of a task
+    #[command(name = "edittask")]
+    EditTask {
+        /// Id or name of the task
+        task: String,
+        /// New name of the task
+        #[arg(short, long)]
+        name: Option<String>,
+        /// New planned time, in format DAYS:HOURS:MINUTES or
+        /// a duration like 1d2h30m
+        #[arg(short = 't', long = "time", value_name = "TIME", value_parser = parse_planned_time)]
+        planned_time: Option<i32>,
+    },
+
+    /// Moves a task to another project
+    #[command(name = "movetask")]
+    MoveTask {
+        /// Id or name of the task
+        task: String,
+        /// Id or name of the project
+        project: String,
+    },
+
+    /// Hides a project from projects
+    #[command(name = "archiveproject")]
+    ArchiveProject {
+        /// Id or name of the project
+        project: String,
+        /// Restores the archived project instead
+        #[arg(short, long)]
+        unarchive: bool,
+    },
+
+    /// Hides a task from tasks
+    #[command(name = "archivetask")]
+    ArchiveTask {
+        /// Id or name of the task
+        task: String,
+        /// Restores the archived task instead
+        #[arg(short, long)]
+        unarchive: bool,
+    },
+
+    /// Deletes a project with its tasks, refused if there are logs for them
+    #[command(name = "deleteproject")]
+    DeleteProject {
+        /// Id or name of the project
+        project: String,
+        /// Deletes the logs as well
+        #[arg(short, long)]
+        cascade: bool,
+    },
+
+    /// Deletes a task, refused if there are logs for it
+    #[command(name = "deletetask")]
+    DeleteTask {
+        /// Id or name of the task
+        task: String,
+        /// Deletes the logs as well
+        #[arg(short, long)]
+        cascade: bool,
+    },
+
+    /// Adds specified apps to db
+    #[command(name = "newapp")]
+    AddApp {
+        /// Names of the apps
+        #[arg(required = true)]
+        apps: Vec<String>,
+    },
+
+    /// Displays all apps with the number of projects using them
+    #[command(name = "apps")]
+    AllApps,
+
+    /// Adds apps to a project, apps that are not in db yet are created
+    #[command(name = "app-project")]
+    AddAppToProject {
+        /// Id or name of the project
+        project: String,
+        /// Names of the apps
+        #[arg(required = true)]
+        apps: Vec<String>,
+        /// Removes the apps from the project instead
+        #[arg(short, long)]
+        remove: bool,
+    },
+
+    /// Adds apps to a task, apps that are not in db yet are created
+    #[command(name = "app-task")]
+    AddAppToTask {
+        /// Id or name of the task
+        task: String,
+        /// Names of the apps
+        #[arg(required = true)]
+        apps: Vec<String>,
+        /// Removes the apps from the task instead
+        #[arg(short, long)]
+        remove: bool,
+    },
+
+    /// Begins a task; a task that has ended cannot be started again
+    Begin(LogArgs),
+
+    /// Pauses a task; a task that has already been paused, has ended or has not begun cannot be paused
+    Pause(LogArgs),
+
+    /// Resumes a task; a task that has not been paused, has ended or has not begun cannot be resumed
+    Resume(LogArgs),
+
+    /// Ends a task; cannot end a task that was not started
+    End(LogArgs),
+
+    /// Begins or resumes a task and pauses the other tasks in progress
+    Switch(LogArgs),
+
+    /// Displays the history of all tasks
+    Logs {
+        /// Displays only the history of the tasks of given ids
+        #[arg(short, long, num_args = 1..)]
+        tasks: Vec<i32>,
+    },
+
+    /// Edits or deletes a log; refused if the task's logs would no longer be in an allowed order
+    Log {
+        #[command(subcommand)]
+        action: LogAction,
+    },
+
+    /// Deletes the most recent log made for one of your tasks
+    Undo,
+
+    /// Displays the tasks in progress: whether they are worked on or paused, for how long and how
+    /// much they were worked on today
+    Status {
+        /// Prints the tasks in a single line, e.g. for a shell prompt or a status bar
+        #[arg(short, long)]
+        short: bool,
+    },
+
+    /// Displays stats for every task and project, i.e. number of pauses made during the task,
+    /// time spent working etc.
+    Stats,
+
+    /// Displays day stats: a table of projects developed on the current day
+    Day {
+        /// Displays additional information for every project developed on the day and tasks within it
+        #[arg(short, long)]
+        long: bool,
+        /// Date to display instead of the current date, in format YYYY-MM-DD
+        #[arg(short, long)]
+        date: Option<NaiveDate>,
+        /// Counts the work and pauses still running up to now
+        #[arg(long)]
+        live: bool,
+    },
+
+    /// Displays a report of the days in a range: the work done in every project on each of the
+    /// days and the totals per project; by default the current week
+    Report(ReportArgs),
+
+    /// Displays the pomodoro rounds of work completed and interrupted in every task on each day
+    /// of a range, by default the current week, with the totals per task and the interruption rate
+    Pomodoros(PeriodArgs),
+
+    /// Displays all apps the projects use
+    #[command(name = "projectapps")]
+    ProjectApps,
+
+    /// Displays all tasks belonging to the projects
+    #[command(name = "projecttasks")]
+    ProjectTasks {
+        /// Displays the tasks only for the projects of given ids
+        #[arg(short, long, num_args = 1..)]
+        projects: Option<Vec<i32>>,
+        /// Displays only the tasks that have been begun
+        #[arg(short, long, conflicts_with = "not_begun")]
+        begun: bool,
+        /// Displays only the tasks that have not been begun
+        #[arg(long, visible_alias = "nb")]
+        not_begun: bool,
+        /// Displays only the tasks that have been ended
+        #[arg(short, long, conflicts_with = "not_ended")]
+        ended: bool,
+        /// Displays only the tasks that have not been ended
+        #[arg(long, visible_alias = "ne")]
+        not_ended: bool,
+    },
+
+    /// Enables pomodoro mode for a task. The task must be started but not ended.
+    ///
+    /// When executed, a screen with a clock, the messages and the stats of the task is opened in
+    /// the terminal. Space stops and starts the clock again, s skips the rest of the work or
+    /// break, q quits, and commands are typed after ':': pause HH:MM:SS and work HH:MM:SS count
+    /// down the given time and then ask if a log resume or pause should be added; task ID/NAME
+    /// changes the task; clear clears the messages; exit quits.
+    ///
+    /// With --cycle the rounds of work and breaks follow each other without commands, and the
+    /// pause and resume logs are written as they begin.
+    ///
+    /// The clock is saved while it runs, so that --resume continues it after the terminal is
+    /// closed. Otherwise a clock left that way is found the next time pomodoro mode is started,
+    /// and one may pause its task at the clock's deadline.
+    ///
+    /// The commands in the [hooks] section of the config file are run when work starts, when
+    /// work or a break ends and when the mode is exited.
+    Pomodoro {
+        /// Id or name of the task
+        #[arg(required_unless_present = "resume")]
+        task: Option<String>,
+        /// Alternates work and short breaks with a long break every few rounds; lengths are given
+        /// in minutes as WORK/BREAK/LONG_BREAKxROUNDS, e.g. 25/5/15x4, by default the pomodoro
+        /// lengths in the config file
+        #[arg(long, value_name = "CYCLE", num_args = 0..=1, value_parser = parse_cycle)]
+        cycle: Option<Option<Cycle>>,
+        /// Continues the pomodoro left by a terminal that was closed or crashed, with its clock,
+        /// round and cycle
+        #[arg(long, conflicts_with_all = ["task", "cycle"])]
+        resume: bool,
+    },
+
+    /// Applies pending database migrations; they are also applied automatically before every
+    /// other command
+    Migrate,
+
+    /// Displays app's manual
+    Man {
+        /// Prints the manual as a roff man page instead, e.g. to install it as app.1
+        #[arg(long)]
+        roff: bool,
+    },
+
+    /// Prints a shell completion script
+    Completions {
+        /// Shell to generate the script for
+        shell: Shell,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+pub struct LogArgs {
+    /// Id or name of the task
+    pub task: String,
+    /// Dates the log at TIME instead of now, either absolute ("YYYY-MM-DD HH:MM") or relative
+    /// (e.g. -20m, -1h30m, -2d); the log can't be earlier than the task's previous log
+    #[arg(long, value_name = "TIME", value_parser = parse_at, allow_hyphen_values = true)]
+    pub at: Option<NaiveDateTime>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ReportArgs {
+    #[command(flatten)]
+    pub period: PeriodArgs,
+    /// Counts the work and pauses still running up to now
+    #[arg(long)]
+    pub live: bool,
+}
+
+/// Range of days of a report, by default the current week.
+#[derive(clap::Args, Debug)]
+pub struct PeriodArgs {
+    /// First day of the range, in format YYYY-MM-DD
+    #[arg(long, conflicts_with_all = ["week", "month", "last"])]
+    pub from: Option<NaiveDate>,
+    /// Last day of the range, in format YYYY-MM-DD; defaults to today
+    #[arg(long, conflicts_with_all = ["week", "month", "last"])]
+    pub to: Option<NaiveDate>,
+    /// Reports the current week, from Monday to Sunday
+    #[arg(long, conflicts_with_all = ["month", "last"])]
+    pub week: bool,
+    /// Reports the current month
+    #[arg(long, conflicts_with = "last")]
+    pub month: bool,
+    /// Reports the given number of days ending today, e.g. 7d
+    #[arg(long, value_name = "DAYS", value_parser = parse_last)]
+    pub last: Option<u32>,
+}
+
+impl PeriodArgs {
+    /// Resolves the options into the first and the last day of the range, both inclusive.
+    pub fn range(&self, today: NaiveDate) -> Result<(NaiveDate, NaiveDate), &'static str> {
+        let range = if let Some(days) = self.last {
+            let first = today
+                .checked_sub_days(Days::new(u64::from(days).saturating_sub(1)))
+                .ok_or("The range reaches too far into the past!")?;
+            (first, today)
+        } else if self.month {
+            let first = today.with_day(1).unwrap();
+            (first, first + Months::new(1) - Days::new(1))
+        } else if self.from.is_some() || self.to.is_some() {
+            let to = self.to.unwrap_or(today);
+            (self.from.unwrap_or(to), to)
+        } else {
+            let monday = today.week(Weekday::Mon).first_day();
+            (monday, monday + Days::new(6))
+        };
+
+        if range.0 > range.1 {
+            return Err("The first day of the range is after the last one!");
+        }
+        Ok(range)
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LogAction {
+    /// Moves the log to the given time
+    Edit {
+        /// Id of the log
+        log_id: i32,
+        /// New time of the log, either absolute ("YYYY-MM-DD HH:MM") or relative (e.g. -20m)
+        #[arg(long, value_name = "TIME", value_parser = parse_at, allow_hyphen_values = true)]
+        at: NaiveDateTime,
+    },
+    /// Deletes the log
+    Delete {
+        /// Id of the log
+        log_id: i32,
+    },
+}
+
+/// Merges a pair of contradictory filter flags into the filter they select, if any.
+pub fn filter(positive: bool, negative: bool) -> Option<bool> {
+    match (positive, negative) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    }
+}
+
+/// Parses a planned time, given as `DAYS:HOURS:MINUTES` or as a duration like `1d2h30m`,
+/// into minutes.
+fn parse_planned_time(input: &str) -> Result<i32, String> {
+    const FORMAT_ERROR: &str =
+        "Wrong time format! It should have format DAYS:HOURS:MINUTES or be a duration like 1d2h30m";
+
+    let time_regex = Regex::new(r"^(\d+):(\d+):(\d+)$").unwrap();
+    let minutes = match time_regex.captures(input) {
+        Some(parts) => {
+            let part = |i: usize| parts[i].parse::<i64>().ok();
+            part(1)
+                .zip(part(2))
+                .zip(part(3))
+                .and_then(|((days, hours), minutes)| {
+                    days.checked_mul(1440)?
+                        .checked_add(hours.checked_mul(60)?)?
+                        .checked_add(minutes)
+                })
+        }
+        None => logs::parse_duration(input).map(|duration| duration.num_minutes()),
+    };
+
+    minutes
+        .and_then(|x| i32::try_from(x).ok())
+        .ok_or_else(|| FORMAT_ERROR.to_string())
+}
+
+fn parse_last(input: &str) -> Result<u32, String> {
+    logs::parse_duration(input)
+        .filter(|duration| {
+            duration.num_days() > 0 && *duration == Duration::days(duration.num_days())
+        })
+        .and_then(|duration| u32::try_from(duration.num_days()).ok())
+        .ok_or_else(|| "It should be a number of days, e.g. 7d".to_string())
+}
+
+fn parse_cycle(input: &str) -> Result<Cycle, String> {
+    Cycle::parse(input).ok_or_else(|| {
+        "It should be minutes of work, break and long break with the number of rounds, e.g. 25/5/15x4"
+            .to_string()
+    })
+}
+
+fn parse_at(input: &str) -> Result<NaiveDateTime, String> {
+    logs::parse_time(input, workflow::config::now()).map_err(String::from)
+}
@@ -10,9 +10,11 @@ use diesel::sql_types::Nullable;
 use diesel::result::Error;
 use std::process;
 
-pub fn add_app(_app_name: &str, display_communicates: bool) -> Result<i32, &'static str> {
-    let connection = &mut establish_connection();
-
+pub fn add_app(
+    _app_name: &str,
+    display_communicates: bool,
+    connection: &mut DbConnection,
+) -> Result<i32, &'static str> {
     let res_app = create_app(connection, _app_name);
 
     match res_app {
@@ -32,9 +34,9 @@ pub fn add_app(_app_name: &str, display_communicates: bool) -> Result<i32, &'sta
 pub fn add_multiple_apps(
     args: &[String],
     display_communicates: bool,
-    connection: &mut PgConnection,
+    connection: &mut DbConnection,
 ) -> Result<Vec<i32>, &'static str> {
-    if args.len() < 1 {
+    if args.is_empty() {
         eprintln!("Too few args");
         process::exit(-1);
     }
@@ -43,8 +45,8 @@ pub fn add_multiple_apps(
 
     for x in args {
         // println!("{}", x);
-        match find_app_body(x, connection) {
-            Ok(None) => match add_app(&(x.to_lowercase()), display_communicates) {
+        match find_app(x, connection) {
+            Ok(None) => match add_app(&(x.to_lowercase()), display_communicates, connection) {
                 Err(x) => return Err(x),
                 Ok(x) => {
                     ids.push(x);
@@ -62,14 +64,9 @@ pub fn add_multiple_apps(
     Ok(ids)
 }
 
-pub fn find_app(_app_name: &str) -> Result<Option<App>, &'static str> {
-    let connection = &mut establish_connection();
-    find_app_body(_app_name, connection)
-}
-
-fn find_app_body(
+pub fn find_app(
     app_name_: &str,
-    connection: &mut PgConnection,
+    connection: &mut DbConnection,
 ) -> Result<Option<App>, &'static str> {
     let app = apps
         .filter(app_name.eq(app_name_.to_lowercase()))
@@ -88,11 +85,10 @@ fn find_app_body(
 
 pub fn get_app_stats(
     _args: &[String],
+    connection: &mut DbConnection,
 ) -> Result<Vec<(App, Option<i32>)>, &'static str> {
     use workflow::schema::project_apps::dsl::project_apps;
 
-    let connection: &mut PgConnection = &mut establish_connection();
-
     let result=
     workflow::schema::apps::dsl::apps
         .left_join(
@@ -112,3 +108,189 @@ pub fn get_app_stats(
         Err(_) => Err("An error occurred while fetching apps"),
     }
 }
+
+/// The project or the task that apps are attached to.
+#[derive(Clone, Copy)]
+enum AppOwner {
+    Project(i32),
+    Task(i32),
+}
+
+impl AppOwner {
+    fn attached_app_ids(self, connection: &mut DbConnection) -> Result<Vec<i32>, Error> {
+        use workflow::schema::{project_apps, task_apps};
+
+        match self {
+            AppOwner::Project(_project_id) => project_apps::table
+                .filter(project_apps::project_id.eq(_project_id))
+                .select(project_apps::app_id)
+                .load(connection),
+            AppOwner::Task(_task_id) => task_apps::table
+                .filter(task_apps::task_id.eq(_task_id))
+                .select(task_apps::app_id)
+                .load(connection),
+        }
+    }
+
+    fn attach(self, _app_id: i32, connection: &mut DbConnection) -> Result<(), Error> {
+        match self {
+            AppOwner::Project(_project_id) => {
+                create_app_detail(connection, _project_id, _app_id).map(|_| ())
+            }
+            AppOwner::Task(_task_id) => {
+                create_task_app_detail(connection, _task_id, _app_id).map(|_| ())
+            }
+        }
+    }
+
+    fn detach(self, app_ids: Vec<i32>, connection: &mut DbConnection) -> Result<usize, Error> {
+        use workflow::schema::{project_apps, task_apps};
+
+        match self {
+            AppOwner::Project(_project_id) => diesel::delete(
+                project_apps::table.filter(
+                    project_apps::project_id
+                        .eq(_project_id)
+                        .and(project_apps::app_id.eq_any(app_ids)),
+                ),
+            )
+            .execute(connection),
+            AppOwner::Task(_task_id) => diesel::delete(
+                task_apps::table.filter(
+                    task_apps::task_id
+                        .eq(_task_id)
+                        .and(task_apps::app_id.eq_any(app_ids)),
+                ),
+            )
+            .execute(connection),
+        }
+    }
+}
+
+/// Attaches the apps to the project, adding the ones that aren't in the database yet.
+/// Returns the number of newly attached apps.
+pub fn attach_apps_to_project(
+    _project_id: i32,
+    app_names: &[String],
+    connection: &mut DbConnection,
+) -> Result<usize, &'static str> {
+    attach_apps(AppOwner::Project(_project_id), app_names, connection)
+}
+
+/// Attaches the apps to the task, adding the ones that aren't in the database yet.
+/// Returns the number of newly attached apps.
+pub fn attach_apps_to_task(
+    _task_id: i32,
+    app_names: &[String],
+    connection: &mut DbConnection,
+) -> Result<usize, &'static str> {
+    attach_apps(AppOwner::Task(_task_id), app_names, connection)
+}
+
+/// Removes the apps from the project. Returns the number of removed apps.
+pub fn detach_apps_from_project(
+    _project_id: i32,
+    app_names: &[String],
+    connection: &mut DbConnection,
+) -> Result<usize, &'static str> {
+    detach_apps(AppOwner::Project(_project_id), app_names, connection)
+}
+
+/// Removes the apps from the task. Returns the number of removed apps.
+pub fn detach_apps_from_task(
+    _task_id: i32,
+    app_names: &[String],
+    connection: &mut DbConnection,
+) -> Result<usize, &'static str> {
+    detach_apps(AppOwner::Task(_task_id), app_names, connection)
+}
+
+fn attach_apps(
+    owner: AppOwner,
+    app_names: &[String],
+    connection: &mut DbConnection,
+) -> Result<usize, &'static str> {
+    let mut error = None;
+    let result = connection.transaction::<_, Error, _>(|connection| {
+        let app_ids = add_multiple_apps(app_names, false, connection).map_err(|x| {
+            error = Some(x);
+            Error::RollbackTransaction
+        })?;
+        let mut attached = owner.attached_app_ids(connection)?;
+
+        let mut attached_num = 0;
+        for _app_id in app_ids {
+            if !attached.contains(&_app_id) {
+                owner.attach(_app_id, connection)?;
+                attached.push(_app_id);
+                attached_num += 1;
+            }
+        }
+        Ok(attached_num)
+    });
+
+    match (result, error) {
+        (Ok(x), _) => Ok(x),
+        (Err(_), Some(x)) => Err(x),
+        (Err(_), None) => Err("Database error occurred"),
+    }
+}
+
+fn detach_apps(
+    owner: AppOwner,
+    app_names: &[String],
+    connection: &mut DbConnection,
+) -> Result<usize, &'static str> {
+    let app_ids = find_app_ids(app_names, connection)?;
+
+    match owner.detach(app_ids, connection) {
+        Ok(x) => Ok(x),
+        Err(_) => Err("Database error occurred"),
+    }
+}
+
+pub fn get_project_apps(
+    _project_id: i32,
+    connection: &mut DbConnection,
+) -> Result<Vec<App>, &'static str> {
+    use workflow::schema::project_apps;
+
+    let result = apps
+        .inner_join(project_apps::table)
+        .filter(project_apps::project_id.eq(_project_id))
+        .order(app_name.asc())
+        .select(App::as_select())
+        .load::<App>(connection);
+
+    match result {
+        Ok(x) => Ok(x),
+        Err(_) => Err("An error occurred while fetching apps"),
+    }
+}
+
+pub fn get_task_apps(_task_id: i32, connection: &mut DbConnection) -> Result<Vec<App>, &'static str> {
+    use workflow::schema::task_apps;
+
+    let result = apps
+        .inner_join(task_apps::table)
+        .filter(task_apps::task_id.eq(_task_id))
+        .order(app_name.asc())
+        .select(App::as_select())
+        .load::<App>(connection);
+
+    match result {
+        Ok(x) => Ok(x),
+        Err(_) => Err("An error occurred while fetching apps"),
+    }
+}
+
+fn find_app_ids(app_names: &[String], connection: &mut DbConnection) -> Result<Vec<i32>, &'static str> {
+    let mut ids = vec![];
+    for x in app_names {
+        match find_app(x, connection)? {
+            Some(a) => ids.push(a.app_id),
+            None => return Err("No such app!"),
+        }
+    }
+    Ok(ids)
+}
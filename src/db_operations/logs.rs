@@ -2,28 +2,32 @@ use diesel::prelude::QueryDsl;
 use diesel::prelude::*;
 use workflow::*;
 
+use chrono::NaiveDateTime;
 use diesel::result::Error;
+use std::fmt;
 use workflow::models::*;
 
-pub fn get_recent_log(_task_id: i32, order: bool) -> Result<Option<Log>, &'static str> {
+pub fn get_recent_log(
+    _task_id: i32,
+    order: bool,
+    connection: &mut DbConnection,
+) -> Result<Option<Log>, &'static str> {
     use self::schema::log::dsl::*;
 
-    let connection: &mut PgConnection = &mut establish_connection();
-
     let result: Result<Vec<Log>, Error> = if order {
         log.filter(task_id.eq(_task_id))
-            .order(date.desc())
+            .order((date.desc(), log_id.desc()))
             .limit(1)
             .load::<Log>(connection)
     } else {
         log.filter(task_id.eq(_task_id))
-            .order(date.asc())
+            .order((date.asc(), log_id.asc()))
             .limit(1)
             .load::<Log>(connection)
     };
 
     match result {
-        Ok(x) if x.len() > 0 => {
+        Ok(x) if !x.is_empty() => {
             let a = x[0].clone();
             Ok(Some(a))
         }
@@ -32,19 +36,16 @@ pub fn get_recent_log(_task_id: i32, order: bool) -> Result<Option<Log>, &'stati
     }
 }
 
-pub fn get_logs(args: &[String]) -> Result<Vec<Log>, Error> {
+pub fn get_logs(task_ids: &[i32], connection: &mut DbConnection) -> Result<Vec<Log>, Error> {
     use workflow::schema::log::dsl::*;
 
-    let connection: &mut PgConnection = &mut establish_connection();
-
-    if args.len() == 0 {
+    if task_ids.is_empty() {
         let result = log.load::<Log>(connection)?;
 
         Ok(result)
     } else {
         let mut logs = vec![];
-        for x in args {
-            let task_id_ = x.parse::<i32>().unwrap_or(-1);
+        for task_id_ in task_ids {
             let result = log.filter(task_id.eq(task_id_)).load::<Log>(connection)?;
             logs.extend(result);
         }
@@ -52,23 +53,333 @@ pub fn get_logs(args: &[String]) -> Result<Vec<Log>, Error> {
     }
 }
 
-pub fn add_log(_task_id: i32, _log_type: String, display_communicates: bool) {
-    let connection = &mut establish_connection();
-
-    let logs = create_log(connection, _task_id, _log_type.clone());
+/// Why a log couldn't be added to a task.
+#[derive(Debug)]
+pub enum LogError {
+    NoSuchTask,
+    NotStarted,
+    AlreadyStarted,
+    Ended,
+    AlreadyPaused,
+    NotPaused,
+    EarlierThanPrevious,
+    RunningSinceLater(i32),
+    NoSuchLog,
+    NothingToUndo,
+    Database(Error),
+}
 
-    match logs {
-        Err(Error::DatabaseError(diesel::result::DatabaseErrorKind::ForeignKeyViolation, _)) => {
-            println!("No such task, create it!")
+impl fmt::Display for LogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LogError::NoSuchTask => write!(f, "No such task, create it!"),
+            LogError::NotStarted => {
+                write!(f, "First begin the task, then perform other operations!")
+            }
+            LogError::AlreadyStarted => write!(f, "Task has already been started"),
+            LogError::Ended => write!(f, "Task has been ended!"),
+            LogError::AlreadyPaused => write!(f, "Task has already been paused"),
+            LogError::NotPaused => write!(f, "Pause task before you resume it"),
+            LogError::EarlierThanPrevious => {
+                write!(f, "The log can't be earlier than the task's previous log")
+            }
+            LogError::RunningSinceLater(x) => write!(
+                f,
+                "Task {} has been running since later than the log, it can't be paused for it",
+                x
+            ),
+            LogError::NoSuchLog => write!(f, "No such log!"),
+            LogError::NothingToUndo => write!(f, "There are no logs of yours to undo"),
+            LogError::Database(_) => write!(f, "Database error while creating the log"),
         }
-        Err(_) => println!("Database error while creating the task"),
-        Ok(log) => {
-            if display_communicates {
-                println!(
-                    "\nSaved log {} for task {} with id {}",
-                    _log_type, _task_id, log.log_id
-                );
+    }
+}
+
+impl From<Error> for LogError {
+    fn from(error: Error) -> Self {
+        LogError::Database(error)
+    }
+}
+
+/// Checks that `log_type` may follow `recent_log`, the latest log of the task
+/// (see "Allowed order of logs" in the README).
+pub fn validate_transition(recent_log: Option<&Log>, log_type: LogType) -> Result<(), LogError> {
+    match (recent_log.map(|x| x.log_type), log_type) {
+        (None, LogType::Begin) => Ok(()),
+        (None, _) => Err(LogError::NotStarted),
+        (Some(LogType::End), _) => Err(LogError::Ended),
+        (Some(_), LogType::Begin) => Err(LogError::AlreadyStarted),
+        (Some(LogType::Pause), LogType::Pause) => Err(LogError::AlreadyPaused),
+        (Some(LogType::Pause), LogType::Resume) => Ok(()),
+        (Some(LogType::Begin | LogType::Resume), LogType::Pause) => Ok(()),
+        (Some(LogType::Begin | LogType::Resume), LogType::Resume) => Err(LogError::NotPaused),
+        (Some(_), LogType::End) => Ok(()),
+    }
+}
+
+/// Checks the whole history of a task, given in chronological order, log by log.
+pub fn validate_sequence(logs: &[Log]) -> Result<(), LogError> {
+    let mut recent_log = None;
+    for x in logs {
+        validate_transition(recent_log, x.log_type)?;
+        recent_log = Some(x);
+    }
+    Ok(())
+}
+
+/// A log added to a task: the task's previous latest log, the new one and the pauses written for
+/// the other tasks that were running.
+pub type LogChange = (Option<Log>, Log, Vec<Log>);
+
+/// Adds a log of `log_type` dated `date` to the task, provided it's a legal successor of the
+/// task's latest log and isn't dated before it. With `pause_others`, a begin or resume log also
+/// pauses the current user's other running tasks at the same time.
+///
+/// The check and the inserts run in one transaction that first locks the task, and the running
+/// tasks it pauses, so concurrent invocations for the same tasks are serialized.
+pub fn add_log(
+    _task_id: i32,
+    log_type: LogType,
+    _date: NaiveDateTime,
+    pause_others: bool,
+    connection: &mut DbConnection,
+) -> Result<LogChange, LogError> {
+    let pause_others = pause_others && matches!(log_type, LogType::Begin | LogType::Resume);
+    let others = match pause_others {
+        true => get_running_task_ids(connection)?,
+        false => vec![],
+    };
+    connection.transaction(|connection| {
+        lock_tasks(others.into_iter().chain([_task_id]).collect(), connection)?;
+        append_log(_task_id, log_type, _date, pause_others, connection)
+    })
+}
+
+/// Begins the task, or resumes it if it's paused, and pauses the current user's other running
+/// tasks at the same time, all in one transaction.
+pub fn switch_task(
+    _task_id: i32,
+    _date: NaiveDateTime,
+    connection: &mut DbConnection,
+) -> Result<LogChange, LogError> {
+    let others = get_running_task_ids(connection)?;
+    connection.transaction(|connection| {
+        lock_tasks(others.into_iter().chain([_task_id]).collect(), connection)?;
+
+        let log_type = match get_task_history(_task_id, connection)?.last() {
+            None => LogType::Begin,
+            Some(x) if x.log_type == LogType::Pause => LogType::Resume,
+            Some(x) if x.log_type == LogType::End => return Err(LogError::Ended),
+            Some(_) => return Err(LogError::AlreadyStarted),
+        };
+        append_log(_task_id, log_type, _date, true, connection)
+    })
+}
+
+/// The part of `add_log` run inside its transaction, once the task and the other running tasks
+/// have been locked.
+fn append_log(
+    _task_id: i32,
+    log_type: LogType,
+    _date: NaiveDateTime,
+    pause_others: bool,
+    connection: &mut DbConnection,
+) -> Result<LogChange, LogError> {
+    let recent_log = get_latest_log(_task_id, connection)?;
+    validate_transition(recent_log.as_ref(), log_type)?;
+    if recent_log.as_ref().is_some_and(|x| x.date > _date) {
+        return Err(LogError::EarlierThanPrevious);
+    }
+
+    let mut paused = vec![];
+    if pause_others && matches!(log_type, LogType::Begin | LogType::Resume) {
+        for other in get_running_logs(connection)? {
+            if other.task_id == _task_id {
+                continue;
+            }
+            // A task begun since the running tasks were locked is only locked now, and any task
+            // may have been paused or ended before its lock was taken, so its state is read again
+            lock_task(other.task_id, connection)?;
+            let Some(other) = get_latest_log(other.task_id, connection)?
+                .filter(|x| matches!(x.log_type, LogType::Begin | LogType::Resume))
+            else {
+                continue;
+            };
+            if other.date > _date {
+                return Err(LogError::RunningSinceLater(other.task_id));
             }
+            paused.push(create_log(
+                connection,
+                other.task_id,
+                LogType::Pause,
+                _date,
+            )?);
         }
     }
+
+    let log = create_log(connection, _task_id, log_type, _date)?;
+    Ok((recent_log, log, paused))
+}
+
+/// The current user's tasks that have logs, each with its logs in chronological order.
+pub fn get_user_task_logs(connection: &mut DbConnection) -> Result<Vec<(Task, Log)>, Error> {
+    use self::schema::log::dsl::*;
+    use self::schema::tasks;
+
+    log.inner_join(tasks::table)
+        .filter(tasks::username.eq(&config::get().username))
+        .order((task_id.asc(), date.asc(), log_id.asc()))
+        .select((Task::as_select(), Log::as_select()))
+        .load::<(Task, Log)>(connection)
+}
+
+/// The latest logs of the current user's tasks that are being worked on, i.e. whose latest log
+/// is a begin or a resume.
+pub fn get_running_logs(connection: &mut DbConnection) -> Result<Vec<Log>, Error> {
+    use self::schema::log::dsl::*;
+    use self::schema::tasks;
+
+    let logs = log
+        .inner_join(tasks::table)
+        .filter(tasks::username.eq(&config::get().username))
+        .order((task_id.asc(), date.asc(), log_id.asc()))
+        .select(Log::as_select())
+        .load::<Log>(connection)?;
+
+    let mut latest: Vec<Log> = vec![];
+    for x in logs {
+        match latest.last_mut() {
+            Some(last) if last.task_id == x.task_id => *last = x,
+            _ => latest.push(x),
+        }
+    }
+    latest.retain(|x| matches!(x.log_type, LogType::Begin | LogType::Resume));
+    Ok(latest)
+}
+
+/// Ids of the current user's running tasks.
+fn get_running_task_ids(connection: &mut DbConnection) -> Result<Vec<i32>, Error> {
+    Ok(get_running_logs(connection)?
+        .into_iter()
+        .map(|x| x.task_id)
+        .collect())
+}
+
+/// The latest log of the task.
+fn get_latest_log(_task_id: i32, connection: &mut DbConnection) -> Result<Option<Log>, Error> {
+    use self::schema::log::dsl::*;
+
+    log.filter(task_id.eq(_task_id))
+        .order((date.desc(), log_id.desc()))
+        .first::<Log>(connection)
+        .optional()
+}
+
+/// Locks the tasks in the order of their ids, so that transactions locking the same tasks can't
+/// deadlock.
+fn lock_tasks(mut task_ids: Vec<i32>, connection: &mut DbConnection) -> Result<(), LogError> {
+    task_ids.sort_unstable();
+    task_ids.dedup();
+    for x in task_ids {
+        lock_task(x, connection)?;
+    }
+    Ok(())
+}
+
+/// Takes a write lock on the task's row until the end of the current transaction.
+///
+/// A no-op update is used instead of `SELECT ... FOR UPDATE` as it locks on both backends:
+/// it row-locks the task in PostgreSQL and takes the database write lock in SQLite.
+fn lock_task(_task_id: i32, connection: &mut DbConnection) -> Result<(), LogError> {
+    use self::schema::tasks::dsl::*;
+
+    let locked = diesel::update(tasks.filter(task_id.eq(_task_id)))
+        .set(task_id.eq(task_id))
+        .execute(connection)?;
+
+    match locked {
+        0 => Err(LogError::NoSuchTask),
+        _ => Ok(()),
+    }
+}
+
+/// Moves the log to `new_date`. Returns the log before and after the change.
+///
+/// The task's history is re-validated with the moved log in the same locking transaction,
+/// so an edit that would break the order of logs is rolled back.
+pub fn edit_log(
+    _log_id: i32,
+    new_date: NaiveDateTime,
+    connection: &mut DbConnection,
+) -> Result<(Log, Log), LogError> {
+    connection.transaction(|connection| {
+        use self::schema::log::dsl::*;
+
+        let old_log = find_log(_log_id, connection)?;
+        lock_task(old_log.task_id, connection)?;
+
+        let new_log = diesel::update(log.filter(log_id.eq(_log_id)))
+            .set(date.eq(new_date))
+            .get_result::<Log>(connection)?;
+
+        validate_sequence(&get_task_history(old_log.task_id, connection)?)?;
+        Ok((old_log, new_log))
+    })
+}
+
+/// Deletes the log, provided the task's remaining history is still in a valid order.
+pub fn delete_log(_log_id: i32, connection: &mut DbConnection) -> Result<Log, LogError> {
+    connection.transaction(|connection| {
+        let old_log = find_log(_log_id, connection)?;
+        lock_task(old_log.task_id, connection)?;
+        remove_log(&old_log, connection)?;
+        Ok(old_log)
+    })
+}
+
+/// Deletes the most recently created log of the current user's tasks.
+pub fn undo_log(connection: &mut DbConnection) -> Result<Log, LogError> {
+    connection.transaction(|connection| {
+        let last_log = {
+            use self::schema::log::dsl::*;
+            use self::schema::tasks;
+
+            log.inner_join(tasks::table)
+                .filter(tasks::username.eq(&config::get().username))
+                .order(log_id.desc())
+                .select(Log::as_select())
+                .first::<Log>(connection)
+                .optional()?
+        };
+
+        let last_log = last_log.ok_or(LogError::NothingToUndo)?;
+        lock_task(last_log.task_id, connection)?;
+        remove_log(&last_log, connection)?;
+        Ok(last_log)
+    })
+}
+
+fn find_log(_log_id: i32, connection: &mut DbConnection) -> Result<Log, LogError> {
+    use self::schema::log::dsl::*;
+
+    log.filter(log_id.eq(_log_id))
+        .first::<Log>(connection)
+        .optional()?
+        .ok_or(LogError::NoSuchLog)
+}
+
+fn remove_log(old_log: &Log, connection: &mut DbConnection) -> Result<(), LogError> {
+    use self::schema::log::dsl::*;
+
+    diesel::delete(log.filter(log_id.eq(old_log.log_id))).execute(connection)?;
+    validate_sequence(&get_task_history(old_log.task_id, connection)?)
+}
+
+/// All logs of the task in chronological order.
+fn get_task_history(_task_id: i32, connection: &mut DbConnection) -> Result<Vec<Log>, Error> {
+    use self::schema::log::dsl::*;
+
+    log.filter(task_id.eq(_task_id))
+        .order((date.asc(), log_id.asc()))
+        .load::<Log>(connection)
 }
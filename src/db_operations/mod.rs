@@ -2,4 +2,5 @@ pub mod apps;
 pub mod projects;
 pub mod tasks;
 pub mod stats;
-pub mod logs;
\ No newline at end of file
+pub mod logs;
+pub mod pomodoros;
\ No newline at end of file
@@ -0,0 +1,115 @@
+use chrono::{Days, NaiveDate};
+use diesel::prelude::*;
+use diesel::result::Error;
+use workflow::models::*;
+use workflow::*;
+
+pub fn add_session(
+    session: &NewPomodoroSession,
+    connection: &mut DbConnection,
+) -> Result<PomodoroSession, &'static str> {
+    use self::schema::pomodoro_sessions;
+
+    diesel::insert_into(pomodoro_sessions::table)
+        .values(session)
+        .get_result::<PomodoroSession>(connection)
+        .map_err(|x| {
+            println!("{}", x);
+            "An error occured while saving pomodoro session"
+        })
+}
+
+/// Sessions started from the first to the last day, both inclusive, with the names of their
+/// tasks, ordered by the start.
+pub fn get_sessions(
+    from: NaiveDate,
+    to: NaiveDate,
+    connection: &mut DbConnection,
+) -> Result<Vec<(PomodoroSession, String)>, &'static str> {
+    use self::schema::{pomodoro_sessions, tasks};
+
+    let sessions = pomodoro_sessions::table
+        .inner_join(tasks::table)
+        .filter(pomodoro_sessions::started_at.ge(from.and_hms_opt(0, 0, 0).unwrap()))
+        .filter(pomodoro_sessions::started_at.lt((to + Days::new(1)).and_hms_opt(0, 0, 0).unwrap()))
+        .order((
+            pomodoro_sessions::started_at.asc(),
+            pomodoro_sessions::session_id.asc(),
+        ))
+        .select((PomodoroSession::as_select(), tasks::task_name))
+        .load::<(PomodoroSession, String)>(connection);
+
+    match sessions {
+        Ok(x) => Ok(x),
+        Err(Error::NotFound) => Ok(vec![]),
+        Err(x) => {
+            println!("{}", x);
+            Err("An error occured while fetching pomodoro sessions")
+        }
+    }
+}
+
+pub fn get_timer(
+    username_: &str,
+    connection: &mut DbConnection,
+) -> Result<Option<PomodoroTimer>, &'static str> {
+    use self::schema::pomodoro_timers::dsl::*;
+
+    pomodoro_timers
+        .filter(username.eq(username_))
+        .select(PomodoroTimer::as_select())
+        .first(connection)
+        .optional()
+        .map_err(|_| "An error occured while fetching pomodoro timer")
+}
+
+/// Saves the user's timer, unless a timer of another owner is saved already. Returns false in
+/// that case.
+pub fn save_timer(
+    timer: &PomodoroTimer,
+    connection: &mut DbConnection,
+) -> Result<bool, &'static str> {
+    use self::schema::pomodoro_timers::dsl::*;
+
+    let result = connection.transaction::<_, Error, _>(|connection| {
+        let saved_owner = pomodoro_timers
+            .filter(username.eq(&timer.username))
+            .select(owner)
+            .first::<String>(connection)
+            .optional()?;
+        match saved_owner {
+            Some(x) if x != timer.owner => return Ok(false),
+            Some(_) => {
+                diesel::update(pomodoro_timers.filter(username.eq(&timer.username)))
+                    .set(timer)
+                    .execute(connection)?;
+            }
+            None => {
+                diesel::insert_into(pomodoro_timers)
+                    .values(timer)
+                    .execute(connection)?;
+            }
+        }
+        Ok(true)
+    });
+
+    result.map_err(|_| "An error occured while saving pomodoro timer")
+}
+
+/// Deletes the user's timer if it belongs to `owner_`.
+pub fn delete_timer(
+    username_: &str,
+    owner_: &str,
+    connection: &mut DbConnection,
+) -> Result<(), &'static str> {
+    use self::schema::pomodoro_timers::dsl::*;
+
+    diesel::delete(
+        pomodoro_timers
+            .filter(username.eq(username_))
+            .filter(owner.eq(owner_)),
+    )
+    .execute(connection)
+    .map(|_| ())
+    .map_err(|_| "An error occured while deleting pomodoro timer")
+}
@@ -1,9 +1,8 @@
 use std::collections::HashMap;
 
 use crate::db_operations;
-use chrono::NaiveDate;
 use diesel::associations::HasTable;
-use diesel::dsl::{date, sql};
+use diesel::dsl::sql;
 use diesel::prelude::QueryDsl;
 use diesel::prelude::*;
 use diesel::result::Error;
@@ -12,27 +11,17 @@ use diesel::sql_types::{Text,Int4};
 use workflow::models::*;
 use workflow::*;
 
-pub fn find_project(project_name_: &str) -> Result<Option<Project>, &'static str> {
-    use self::schema::projects::dsl::*;
-    let connection = &mut establish_connection();
-    let app = projects
-        .filter(project_name.eq(project_name_.to_lowercase()))
-        .select(Project::as_select())
-        .first(connection);
+/// A project joined with one of its tasks: `(project, task_name, planned_minutes, task_id)`.
+pub type ProjectTaskRow = (Project, Option<String>, Option<i32>, Option<i32>);
 
-    match app {
-        Ok(x) => Ok(Some(x)),
-        Err(Error::NotFound) => Ok(None),
-        Err(x) => {
-            println!("{}", x);
-            Err("An error occured while fetching project")
-        }
-    }
-}
+/// A project joined with one of its apps: `(project, app_name, app_id)`.
+pub type ProjectAppRow = (Project, Option<String>, Option<i32>);
 
-pub fn get_project_by_id(project_id_: i32) -> Result<Option<Project>, &'static str> {
+pub fn get_project_by_id(
+    project_id_: i32,
+    connection: &mut DbConnection,
+) -> Result<Option<Project>, &'static str> {
     use self::schema::projects::dsl::*;
-    let connection = &mut establish_connection();
     let app = projects
         .filter(project_id.eq(project_id_))
         .select(Project::as_select())
@@ -48,47 +37,25 @@ pub fn get_project_by_id(project_id_: i32) -> Result<Option<Project>, &'static s
     }
 }
 
-pub fn get_projects() -> Result<Vec<Project>, Error> {
+pub fn get_projects(connection: &mut DbConnection) -> Result<Vec<Project>, Error> {
     use self::schema::projects::dsl::projects;
 
-    let connection = &mut establish_connection();
     let projects_list = projects.load::<Project>(connection)?;
 
     Ok(projects_list)
 }
 
-pub fn get_date_projects(date_to_seek: NaiveDate) -> Result<Vec<Project>, Error> {
-    use self::schema::log::dsl::log;
-    use self::schema::projects::dsl::projects;
-    use self::schema::tasks::dsl::tasks;
-
-    let connection = &mut establish_connection();
-    let projects_list = tasks
-        .inner_join(log.on(workflow::schema::log::task_id.eq(workflow::schema::tasks::task_id)))
-        .inner_join(
-            projects
-                .on(workflow::schema::projects::project_id.eq(workflow::schema::tasks::project_id)),
-        )
-        .filter(date(workflow::schema::log::date).eq(date_to_seek))
-        .order(workflow::schema::projects::project_id.asc())
-        .select(Project::as_select())
-        .distinct()
-        .load::<Project>(connection)?;
-
-    Ok(projects_list)
-}
-
 pub fn add_project(
     project_name_: &str,
-    planned_time: Option<&str>,
+    planned_minutes: Option<i32>,
     project_apps: Option<&[String]>,
     display_communicates: bool,
+    connection: &mut DbConnection,
 ) -> Result<i32, &'static str> {
-    let connection = &mut establish_connection();
     let mut project_id = 0;
 
     match connection.transaction::<_, Error, _>(|connection| {
-        let project = match create_project(connection, project_name_, planned_time) {
+        let project = match create_project(connection, project_name_, planned_minutes) {
             Ok(x) => x,
             Err(e) => {
                 return Err(e);
@@ -108,7 +75,7 @@ pub fn add_project(
 
             println!("{:?}", app_ids);
             for _app_id in app_ids {
-                create_app_detail(connection, project_id, _app_id);
+                create_app_detail(connection, project_id, _app_id)?;
             }
         }
         Ok("")
@@ -121,19 +88,105 @@ pub fn add_project(
             Ok(project_id)
         }
         Err(Error::DatabaseError(diesel::result::DatabaseErrorKind::UniqueViolation, _)) => {
-            return Err("Project of such name is already in the database, choose another name!");
+            Err("Project of such name is already in the database, choose another name!")
+        }
+        Err(x) => {
+            println!("{}", x);
+            Err("Database error occurred")
         }
+    }
+}
+
+pub fn edit_project(
+    _project_id: i32,
+    changes: &ProjectChanges,
+    connection: &mut DbConnection,
+) -> Result<Project, &'static str> {
+    use self::schema::projects::dsl::*;
+
+    let result = diesel::update(projects.filter(project_id.eq(_project_id)))
+        .set(changes)
+        .get_result::<Project>(connection);
+
+    match result {
+        Ok(x) => Ok(x),
+        Err(Error::NotFound) => Err("No such project!"),
+        Err(Error::DatabaseError(diesel::result::DatabaseErrorKind::UniqueViolation, _)) => {
+            Err("Project of such name is already in the database, choose another name!")
+        }
+        Err(x) => {
+            println!("{}", x);
+            Err("Database error occurred")
+        }
+    }
+}
+
+/// Deletes the project with its tasks and app assignments. If any of the tasks has logs, the
+/// project is deleted, together with the logs, only if `cascade` is set.
+/// Returns the number of deleted tasks and logs.
+pub fn delete_project(
+    _project_id: i32,
+    cascade: bool,
+    connection: &mut DbConnection,
+) -> Result<(usize, usize), &'static str> {
+    use workflow::schema::{
+        log, pomodoro_sessions, pomodoro_timers, project_apps, projects, task_apps, tasks,
+    };
+
+    let result = connection.transaction::<_, Error, _>(|connection| {
+        let project_tasks = tasks::table
+            .filter(tasks::project_id.eq(_project_id))
+            .select(tasks::task_id);
+
+        let logs_num: i64 = log::table
+            .filter(log::task_id.eq_any(project_tasks))
+            .count()
+            .get_result(connection)?;
+        if logs_num > 0 && !cascade {
+            return Ok(None);
+        }
+
+        let deleted_logs = diesel::delete(log::table.filter(log::task_id.eq_any(project_tasks)))
+            .execute(connection)?;
+        diesel::delete(task_apps::table.filter(task_apps::task_id.eq_any(project_tasks)))
+            .execute(connection)?;
+        diesel::delete(
+            pomodoro_sessions::table.filter(pomodoro_sessions::task_id.eq_any(project_tasks)),
+        )
+        .execute(connection)?;
+        diesel::delete(
+            pomodoro_timers::table.filter(pomodoro_timers::task_id.eq_any(project_tasks)),
+        )
+        .execute(connection)?;
+        let deleted_tasks =
+            diesel::delete(tasks::table.filter(tasks::project_id.eq(_project_id)))
+                .execute(connection)?;
+        diesel::delete(project_apps::table.filter(project_apps::project_id.eq(_project_id)))
+            .execute(connection)?;
+        match diesel::delete(projects::table.filter(projects::project_id.eq(_project_id)))
+            .execute(connection)?
+        {
+            0 => Err(Error::NotFound),
+            _ => Ok(Some((deleted_tasks, deleted_logs))),
+        }
+    });
+
+    match result {
+        Ok(Some(x)) => Ok(x),
+        Ok(None) => Err("Project's tasks have logs, delete it with -c to remove them as well"),
+        Err(Error::NotFound) => Err("No such project!"),
         Err(x) => {
             println!("{}", x);
-            return Err("Database error occurred");
+            Err("Database error occurred")
         }
     }
 }
 
-pub fn get_apps_in_projects() -> Result<Vec<(Project, Option<String>, Option<i32>)>, &'static str> {
+pub fn get_apps_in_projects(
+    connection: &mut DbConnection,
+) -> Result<Vec<ProjectAppRow>, &'static str> {
     use workflow::schema::*;
 
-    let connection: &mut PgConnection = &mut establish_connection();
 
     let result = projects::dsl::projects
         .left_join(
@@ -144,7 +197,7 @@ pub fn get_apps_in_projects() -> Result<Vec<(Project, Option<String>, Option<i32
         .order(projects::dsl::project_id.asc())
         .select((Project::as_select(), sql::<Nullable<Text>>("apps.app_name"),sql::<Nullable<Int4>>("apps.app_id")))
         .distinct()
-        .load::<(Project, Option<String>, Option<i32>)>(connection);
+        .load::<ProjectAppRow>(connection);
 
     match result {
         Ok(x) => Ok(x),
@@ -154,11 +207,11 @@ pub fn get_apps_in_projects() -> Result<Vec<(Project, Option<String>, Option<i32
 
 pub fn get_tasks_in_projects(
     project_ids: Option<Vec<i32>>,
-    commands: HashMap<String,bool>,
-) -> Result<Vec<(Project, Option<String>, Option<String>, Option<i32>)>, &'static str> {
+    commands: HashMap<LogType,bool>,
+    connection: &mut DbConnection,
+) -> Result<Vec<ProjectTaskRow>, &'static str> {
     use workflow::schema::*;
 
-    let connection: &mut PgConnection = &mut establish_connection();
     let command_list=commands.clone();
 
     let mut result =
@@ -189,9 +242,8 @@ pub fn get_tasks_in_projects(
     //         .into_boxed()
     // };
 
-    if project_ids.is_some() {
-        let seeked_ids = project_ids.unwrap();
-        result = result.filter(workflow::schema::tasks::project_id.eq_any(seeked_ids.clone()));
+    if let Some(seeked_ids) = project_ids {
+        result = result.filter(workflow::schema::tasks::project_id.eq_any(seeked_ids));
     }
     // if let Some(command_list)=commands{
         for (command,negation) in command_list{
@@ -209,11 +261,11 @@ pub fn get_tasks_in_projects(
         .select((
             Project::as_select(),
             sql::<Nullable<Text>>("tasks.task_name"),
-            sql::<Nullable<Text>>("tasks.planned_time"),
+            sql::<Nullable<Int4>>("tasks.planned_minutes"),
             sql::<Nullable<Int4>>("tasks.task_id"),
         ))
         .distinct()
-        .load::<(Project, Option<String>, Option<String>, Option<i32>)>(connection);
+        .load::<ProjectTaskRow>(connection);
 
     match result {
         Ok(x) => Ok(x),
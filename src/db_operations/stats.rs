@@ -1,7 +1,5 @@
-use chrono::prelude::*;
 use chrono::NaiveDateTime;
 use diesel::associations::HasTable;
-use diesel::dsl::date;
 use diesel::dsl::sql;
 use diesel::prelude::QueryDsl;
 use diesel::prelude::*;
@@ -14,21 +12,24 @@ use workflow::*;
 use diesel::result::Error;
 use workflow::models::*;
 
+/// A task joined with one of its logs: `(task, log_id, log_type, date)`.
+pub type TaskLogRow = (Task, Option<i32>, Option<LogType>, Option<NaiveDateTime>);
+
 pub fn get_stats(
     _args: &[String],
-) -> Result<Vec<(Task, Option<i32>, Option<String>, Option<NaiveDateTime>)>, &'static str> {
+    connection: &mut DbConnection,
+) -> Result<Vec<TaskLogRow>, &'static str> {
     use workflow::schema::log::dsl::log;
     use workflow::schema::tasks::dsl::tasks;
 
-    let connection: &mut PgConnection = &mut establish_connection();
-
-    let result: Result<Vec<(Task, Option<i32>, Option<String>, Option<NaiveDateTime>)>, Error> =
+    let result: Result<Vec<TaskLogRow>, Error> =
         tasks::table()
             .left_join(log.on(workflow::schema::log::task_id.eq(workflow::schema::tasks::task_id)))
             .order((
                 workflow::schema::tasks::project_id.asc(),
                 workflow::schema::tasks::task_id.asc(),
                 workflow::schema::log::date.asc(),
+                workflow::schema::log::log_id.asc(),
             ))
             .select((
                 Task::as_select(),
@@ -36,7 +37,7 @@ pub fn get_stats(
                 sql::<Nullable<Text>>("log.log_type"),
                 sql::<Nullable<Timestamp>>("log.date"),
             ))
-            .load::<(Task, Option<i32>, Option<String>, Option<NaiveDateTime>)>(connection);
+            .load::<TaskLogRow>(connection);
 
     match result {
         Ok(x) => Ok(x),
@@ -44,27 +45,27 @@ pub fn get_stats(
     }
 }
 
-pub fn get_day_stats_tasks(
-    date_to_seek: NaiveDate,
+/// Tasks, of the project when given, joined with their whole history of logs, which the stats of
+/// a range are clipped from: work running past the end of the range is closed by a later log.
+pub fn get_stats_tasks(
     seeked_project_id: Option<i32>,
-) -> Result<Vec<(Task, Option<i32>, Option<String>, Option<NaiveDateTime>)>, &'static str> {
+    connection: &mut DbConnection,
+) -> Result<Vec<TaskLogRow>, &'static str> {
     use workflow::schema::log::dsl::log;
     use workflow::schema::tasks::dsl::tasks;
 
-    let connection: &mut PgConnection = &mut establish_connection();
-
     let mut result = tasks::table()
         .left_join(log.on(workflow::schema::log::task_id.eq(workflow::schema::tasks::task_id)))
         .order((
             workflow::schema::tasks::project_id.asc(),
             workflow::schema::tasks::task_id.asc(),
             workflow::schema::log::date.asc(),
+            workflow::schema::log::log_id.asc(),
         ))
         .into_boxed();
-    if let Some(_) = seeked_project_id {
-        result = result.filter(workflow::schema::tasks::project_id.eq(seeked_project_id.unwrap()));
+    if let Some(seeked_project_id) = seeked_project_id {
+        result = result.filter(workflow::schema::tasks::project_id.eq(seeked_project_id));
     }
-    result = result.filter(date(workflow::schema::log::date).eq(date_to_seek));
 
     let result = result
         .select((
@@ -73,7 +74,7 @@ pub fn get_day_stats_tasks(
             sql::<Nullable<Text>>("log.log_type"),
             sql::<Nullable<Timestamp>>("log.date"),
         ))
-        .load::<(Task, Option<i32>, Option<String>, Option<NaiveDateTime>)>(connection);
+        .load::<TaskLogRow>(connection);
     match result {
         Ok(x) => Ok(x),
         Err(_) => Err("An error occurred while fetching day stats"),
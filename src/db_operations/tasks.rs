@@ -5,18 +5,19 @@ use diesel::prelude::QueryDsl;
 use diesel::result::Error;
 use workflow::models::*;
 
-pub fn find_task(task_name_: &str) -> Result<Option<Task>, &'static str> {
-    
-    use self::schema::tasks::dsl::*;
-    let connection = &mut establish_connection();
-    let app = tasks
-        .filter(task_name.eq(task_name_.to_lowercase()))
-        .select(Task::as_select())
-        .first(connection);
+/// Every task with the name of its project, for finding tasks by name.
+pub fn get_tasks_with_project_names(
+    connection: &mut DbConnection,
+) -> Result<Vec<(Task, String)>, &'static str> {
+    use self::schema::{projects, tasks};
+    let app = tasks::table
+        .inner_join(projects::table)
+        .order(tasks::task_id.asc())
+        .select((Task::as_select(), projects::project_name))
+        .load::<(Task, String)>(connection);
 
     match app {
-        Ok(x) => Ok(Some(x)),
-        Err(Error::NotFound) => Ok(None),
+        Ok(x) => Ok(x),
         Err(x) => {
             println!("{}", x);
             Err("An error occured while fetching task")
@@ -24,10 +25,11 @@ pub fn find_task(task_name_: &str) -> Result<Option<Task>, &'static str> {
     }
 }
 
-pub fn find_task_by_id(task_id_: &i32) -> Result<Option<Task>, &'static str> {
-    
+pub fn find_task_by_id(
+    task_id_: &i32,
+    connection: &mut DbConnection,
+) -> Result<Option<Task>, &'static str> {
     use self::schema::tasks::dsl::*;
-    let connection = &mut establish_connection();
     let app = tasks
         .filter(task_id.eq(task_id_))
         .select(Task::as_select())
@@ -43,11 +45,19 @@ pub fn find_task_by_id(task_id_: &i32) -> Result<Option<Task>, &'static str> {
     }
 }
 
-pub fn get_tasks() -> Result<Vec<Task>, Error> {
-    use self::schema::tasks::dsl::tasks;
+pub fn get_tasks(include_archived: bool, connection: &mut DbConnection) -> Result<Vec<Task>, Error> {
+    use self::schema::{projects, tasks};
+
+    let mut query = tasks::table
+        .inner_join(projects::table)
+        .order(tasks::task_id.asc())
+        .select(Task::as_select())
+        .into_boxed();
+    if !include_archived {
+        query = query.filter(tasks::archived.eq(false).and(projects::archived.eq(false)));
+    }
 
-    let connection = &mut establish_connection();
-    let tasks_list = tasks.load::<Task>(connection)?;
+    let tasks_list = query.load::<Task>(connection)?;
 
     Ok(tasks_list)
 }
@@ -55,14 +65,14 @@ pub fn get_tasks() -> Result<Vec<Task>, Error> {
 pub fn add_task(
     project_id:i32,
     task_name_: &str,
-    planned_time: Option<&str>,
+    planned_minutes: Option<i32>,
     display_communicates: bool,
+    connection: &mut DbConnection,
 ) -> Result<i32, &'static str> {
-    let connection = &mut establish_connection();
     let mut task_id = 0;
 
     match connection.transaction::<_, Error, _>(|connection| {
-        let task = match create_task(connection,project_id, task_name_, planned_time) {
+        let task = match create_task(connection,project_id, task_name_, planned_minutes) {
             Ok(x) => x,
             Err(e) => {
                 return Err(e);
@@ -82,12 +92,78 @@ pub fn add_task(
             Ok(task_id)
         }
         Err(Error::DatabaseError(diesel::result::DatabaseErrorKind::UniqueViolation, _x)) => {
-
-            return Err("Task of such name is already in this project, choose another name!");
+            Err("Task of such name is already in this project, choose another name!")
         },
         Err(_x) => {
-            return Err("Database error occurred");
+            Err("Database error occurred")
         }
     }
 }
 
+pub fn edit_task(
+    _task_id: i32,
+    changes: &TaskChanges,
+    connection: &mut DbConnection,
+) -> Result<Task, &'static str> {
+    use self::schema::tasks::dsl::*;
+
+    let result = diesel::update(tasks.filter(task_id.eq(_task_id)))
+        .set(changes)
+        .get_result::<Task>(connection);
+
+    match result {
+        Ok(x) => Ok(x),
+        Err(Error::NotFound) => Err("No such task!"),
+        Err(Error::DatabaseError(diesel::result::DatabaseErrorKind::UniqueViolation, _)) => {
+            Err("Task of such name is already in this project, choose another name!")
+        }
+        Err(x) => {
+            println!("{}", x);
+            Err("Database error occurred")
+        }
+    }
+}
+
+/// Deletes the task. A task with logs is deleted, together with its logs, only if `cascade` is set.
+/// Returns the number of deleted logs.
+pub fn delete_task(
+    _task_id: i32,
+    cascade: bool,
+    connection: &mut DbConnection,
+) -> Result<usize, &'static str> {
+    use self::schema::tasks::dsl::*;
+    use self::schema::{log, pomodoro_sessions, pomodoro_timers, task_apps};
+
+    let result = connection.transaction::<_, Error, _>(|connection| {
+        let logs_num: i64 = log::table
+            .filter(log::task_id.eq(_task_id))
+            .count()
+            .get_result(connection)?;
+        if logs_num > 0 && !cascade {
+            return Ok(None);
+        }
+
+        let deleted_logs =
+            diesel::delete(log::table.filter(log::task_id.eq(_task_id))).execute(connection)?;
+        diesel::delete(task_apps::table.filter(task_apps::task_id.eq(_task_id)))
+            .execute(connection)?;
+        diesel::delete(pomodoro_sessions::table.filter(pomodoro_sessions::task_id.eq(_task_id)))
+            .execute(connection)?;
+        diesel::delete(pomodoro_timers::table.filter(pomodoro_timers::task_id.eq(_task_id)))
+            .execute(connection)?;
+        match diesel::delete(tasks.filter(task_id.eq(_task_id))).execute(connection)? {
+            0 => Err(Error::NotFound),
+            _ => Ok(Some(deleted_logs)),
+        }
+    });
+
+    match result {
+        Ok(Some(x)) => Ok(x),
+        Ok(None) => Err("Task has logs, delete it with -c to remove them as well"),
+        Err(Error::NotFound) => Err("No such task!"),
+        Err(x) => {
+            println!("{}", x);
+            Err("Database error occurred")
+        }
+    }
+}
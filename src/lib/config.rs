@@ -0,0 +1,406 @@
+//! Defaults and preferences read from `~/.config/workflow/config.toml`.
+//!
+//! The file is optional and every setting in it can be overridden by an environment variable
+//! (also set in `.env`), e.g. `DATABASE_URL` or `WORKFLOW_DATE_FORMAT`. The configuration is read
+//! once, by `load` at the start of the app, and shared by all modules through `get`.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Local, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use dotenvy::dotenv;
+use serde::Deserialize;
+
+/// Settings as written in the file, all of them optional.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    database_url: Option<String>,
+    username: Option<String>,
+    format: Option<String>,
+    time_zone: Option<String>,
+    date_format: Option<String>,
+    default_project: Option<String>,
+    one_active_task: Option<bool>,
+    pomodoro: PomodoroFile,
+    hooks: HooksFile,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct PomodoroFile {
+    work: Option<String>,
+    pause: Option<String>,
+    long_break: Option<String>,
+    rounds: Option<u32>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct HooksFile {
+    work_started: Option<String>,
+    work_finished: Option<String>,
+    break_finished: Option<String>,
+    session_ended: Option<String>,
+}
+
+/// Commands run with `sh -c` on the events of the pomodoro mode.
+#[derive(Debug, Clone)]
+pub struct Hooks {
+    pub work_started: Option<String>,
+    /// Run when work is counted down, skipped or interrupted.
+    pub work_finished: Option<String>,
+    /// Run when a break is counted down, skipped or interrupted.
+    pub break_finished: Option<String>,
+    /// Run when the pomodoro mode is quit.
+    pub session_ended: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Database to connect to, see `DbConnection`.
+    pub database_url: Option<String>,
+    /// Owner of the created tasks and projects, by default the user logged in.
+    pub username: String,
+    /// Default of the `--format` option, checked by the app.
+    pub format: Option<String>,
+    /// Time zone of the logs made now, by default the system's.
+    pub time_zone: Option<Tz>,
+    /// Format of the dates displayed, in `strftime` syntax.
+    pub date_format: String,
+    /// Project, given by id or name, of the tasks created without one.
+    pub default_project: Option<String>,
+    /// Whether beginning or resuming a task pauses the other running tasks.
+    pub one_active_task: bool,
+    /// Time counted down by the pomodoro `work` command without an argument.
+    pub pomodoro_work: NaiveTime,
+    /// Time counted down by the pomodoro `pause` command without an argument.
+    pub pomodoro_pause: NaiveTime,
+    /// Break taken instead of the pause after every `pomodoro_rounds` rounds of a pomodoro cycle.
+    pub pomodoro_long_break: NaiveTime,
+    pub pomodoro_rounds: u32,
+    pub hooks: Hooks,
+}
+
+impl Config {
+    /// Current date and time in the configured time zone.
+    pub fn now(&self) -> NaiveDateTime {
+        match self.time_zone {
+            Some(tz) => Utc::now().with_timezone(&tz).naive_local(),
+            None => Local::now().naive_local(),
+        }
+    }
+
+    /// Applies the environment variables over the settings of the file.
+    fn from_file(file: ConfigFile) -> Result<Config, String> {
+        let time_zone = match var("WORKFLOW_TIME_ZONE").or(file.time_zone) {
+            Some(x) => Some(
+                x.parse::<Tz>()
+                    .map_err(|_| format!("Unknown time zone {}", x))?,
+            ),
+            None => None,
+        };
+
+        let date_format = var("WORKFLOW_DATE_FORMAT")
+            .or(file.date_format)
+            .unwrap_or_else(|| "%Y-%m-%d %H:%M:%S".to_string());
+        if StrftimeItems::new(&date_format).any(|x| x == Item::Error) {
+            return Err(format!("Invalid date format {}", date_format));
+        }
+
+        let one_active_task = match var("WORKFLOW_ONE_ACTIVE_TASK") {
+            Some(x) => match x.to_lowercase().as_str() {
+                "1" | "true" | "yes" => true,
+                "0" | "false" | "no" => false,
+                _ => return Err(format!("One active task {} should be true or false", x)),
+            },
+            None => file.one_active_task.unwrap_or(false),
+        };
+
+        let pomodoro_rounds = match var("WORKFLOW_POMODORO_ROUNDS") {
+            Some(x) => x
+                .parse::<u32>()
+                .map_err(|_| format!("Number of pomodoro rounds {} should be a number", x))?,
+            None => file.pomodoro.rounds.unwrap_or(4),
+        };
+        if pomodoro_rounds == 0 {
+            return Err("Number of pomodoro rounds should be at least 1".to_string());
+        }
+
+        Ok(Config {
+            database_url: var("DATABASE_URL").or(file.database_url),
+            username: var("WORKFLOW_USERNAME")
+                .or(file.username)
+                .unwrap_or_else(whoami::username),
+            format: var("WORKFLOW_FORMAT").or(file.format),
+            time_zone,
+            date_format,
+            default_project: var("WORKFLOW_DEFAULT_PROJECT").or(file.default_project),
+            one_active_task,
+            pomodoro_work: parse_length(
+                var("WORKFLOW_POMODORO_WORK").or(file.pomodoro.work),
+                "00:25:00",
+            )?,
+            pomodoro_pause: parse_length(
+                var("WORKFLOW_POMODORO_PAUSE").or(file.pomodoro.pause),
+                "00:05:00",
+            )?,
+            pomodoro_long_break: parse_length(
+                var("WORKFLOW_POMODORO_LONG_BREAK").or(file.pomodoro.long_break),
+                "00:15:00",
+            )?,
+            pomodoro_rounds,
+            hooks: Hooks {
+                work_started: var("WORKFLOW_HOOK_WORK_STARTED").or(file.hooks.work_started),
+                work_finished: var("WORKFLOW_HOOK_WORK_FINISHED").or(file.hooks.work_finished),
+                break_finished: var("WORKFLOW_HOOK_BREAK_FINISHED").or(file.hooks.break_finished),
+                session_ended: var("WORKFLOW_HOOK_SESSION_ENDED").or(file.hooks.session_ended),
+            },
+        })
+    }
+}
+
+/// Returns the environment variable, treating an empty one as unset.
+fn var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|x| !x.is_empty())
+}
+
+/// Parses a length of a pomodoro phase, which can't be zero as it would end at once.
+fn parse_length(value: Option<String>, default: &str) -> Result<NaiveTime, String> {
+    let value = value.unwrap_or_else(|| default.to_string());
+    match NaiveTime::parse_from_str(&value, "%H:%M:%S") {
+        Ok(NaiveTime::MIN) => Err(format!(
+            "Pomodoro length {} should be longer than zero",
+            value
+        )),
+        Ok(x) => Ok(x),
+        Err(_) => Err(format!(
+            "Pomodoro length {} should be in format HH:MM:SS",
+            value
+        )),
+    }
+}
+
+/// Path of the config file: `WORKFLOW_CONFIG`, or `workflow/config.toml` in `XDG_CONFIG_HOME`
+/// or in `~/.config`.
+pub fn path() -> Option<PathBuf> {
+    if let Some(x) = var("WORKFLOW_CONFIG") {
+        return Some(PathBuf::from(x));
+    }
+    let dir = match var("XDG_CONFIG_HOME") {
+        Some(x) => PathBuf::from(x),
+        None => PathBuf::from(var("HOME")?).join(".config"),
+    };
+    Some(dir.join("workflow").join("config.toml"))
+}
+
+fn read() -> Result<Config, String> {
+    dotenv().ok();
+
+    let file = match path() {
+        Some(path) if path.exists() => {
+            let content = fs::read_to_string(&path)
+                .map_err(|x| format!("Error reading {}: {}", path.display(), x))?;
+            toml::from_str(&content).map_err(|x| format!("Error in {}: {}", path.display(), x))?
+        }
+        _ => ConfigFile::default(),
+    };
+    Config::from_file(file)
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Reads the config file and the environment, unless they have been read already.
+pub fn load() -> Result<&'static Config, String> {
+    if let Some(x) = CONFIG.get() {
+        return Ok(x);
+    }
+    let config = read()?;
+    Ok(CONFIG.get_or_init(|| config))
+}
+
+/// Returns the configuration, reading it first if `load` hasn't been called.
+pub fn get() -> &'static Config {
+    load().unwrap_or_else(|x| panic!("{}", x))
+}
+
+/// Current date and time in the configured time zone.
+pub fn now() -> NaiveDateTime {
+    get().now()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// The environment is shared by the tests running in parallel.
+    static ENV: Mutex<()> = Mutex::new(());
+
+    const VARS: [&str; 12] = [
+        "DATABASE_URL",
+        "WORKFLOW_USERNAME",
+        "WORKFLOW_FORMAT",
+        "WORKFLOW_TIME_ZONE",
+        "WORKFLOW_DATE_FORMAT",
+        "WORKFLOW_DEFAULT_PROJECT",
+        "WORKFLOW_ONE_ACTIVE_TASK",
+        "WORKFLOW_POMODORO_WORK",
+        "WORKFLOW_POMODORO_PAUSE",
+        "WORKFLOW_POMODORO_LONG_BREAK",
+        "WORKFLOW_POMODORO_ROUNDS",
+        "WORKFLOW_HOOK_WORK_STARTED",
+    ];
+
+    /// Reads the config from the file with only the given variables set.
+    fn config(file: &str, vars: &[(&str, &str)]) -> Result<Config, String> {
+        let _lock = ENV.lock().unwrap_or_else(|x| x.into_inner());
+        for name in VARS {
+            env::remove_var(name);
+        }
+        for (name, value) in vars {
+            env::set_var(name, value);
+        }
+        let result = Config::from_file(toml::from_str(file).unwrap());
+        for (name, _) in vars {
+            env::remove_var(name);
+        }
+        result
+    }
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn uses_defaults_without_file_and_variables() {
+        let config = config("", &[]).unwrap();
+
+        assert_eq!(config.database_url, None);
+        assert_eq!(config.date_format, "%Y-%m-%d %H:%M:%S");
+        assert!(!config.one_active_task);
+        assert_eq!(config.pomodoro_work, time(0, 25));
+        assert_eq!(config.pomodoro_pause, time(0, 5));
+        assert_eq!(config.pomodoro_long_break, time(0, 15));
+        assert_eq!(config.pomodoro_rounds, 4);
+        assert_eq!(config.hooks.work_started, None);
+    }
+
+    #[test]
+    fn reads_settings_from_file() {
+        let file = r#"
+            username = "me"
+            date_format = "%d.%m %H:%M"
+            one_active_task = true
+
+            [pomodoro]
+            work = "00:50:00"
+            rounds = 3
+
+            [hooks]
+            work_started = "notify-send work"
+        "#;
+        let config = config(file, &[]).unwrap();
+
+        assert_eq!(config.username, "me");
+        assert_eq!(config.date_format, "%d.%m %H:%M");
+        assert!(config.one_active_task);
+        assert_eq!(config.pomodoro_work, time(0, 50));
+        assert_eq!(config.pomodoro_rounds, 3);
+        assert_eq!(
+            config.hooks.work_started.as_deref(),
+            Some("notify-send work")
+        );
+    }
+
+    #[test]
+    fn variables_override_file() {
+        let file = r#"
+            username = "me"
+            time_zone = "Europe/Warsaw"
+            one_active_task = true
+
+            [pomodoro]
+            work = "00:50:00"
+            rounds = 3
+
+            [hooks]
+            work_started = "notify-send work"
+        "#;
+        let config = config(
+            file,
+            &[
+                ("WORKFLOW_USERNAME", "you"),
+                ("WORKFLOW_TIME_ZONE", "Asia/Tokyo"),
+                ("WORKFLOW_ONE_ACTIVE_TASK", "No"),
+                ("WORKFLOW_POMODORO_WORK", "00:20:00"),
+                ("WORKFLOW_POMODORO_ROUNDS", "6"),
+                ("WORKFLOW_HOOK_WORK_STARTED", "paplay bell.oga"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(config.username, "you");
+        assert_eq!(config.time_zone, Some(chrono_tz::Asia::Tokyo));
+        assert!(!config.one_active_task);
+        assert_eq!(config.pomodoro_work, time(0, 20));
+        assert_eq!(config.pomodoro_rounds, 6);
+        assert_eq!(
+            config.hooks.work_started.as_deref(),
+            Some("paplay bell.oga")
+        );
+    }
+
+    #[test]
+    fn empty_variables_are_unset() {
+        let file = r#"
+            username = "me"
+
+            [pomodoro]
+            rounds = 3
+        "#;
+        let config = config(
+            file,
+            &[("WORKFLOW_USERNAME", ""), ("WORKFLOW_POMODORO_ROUNDS", "")],
+        )
+        .unwrap();
+
+        assert_eq!(config.username, "me");
+        assert_eq!(config.pomodoro_rounds, 3);
+    }
+
+    #[test]
+    fn rejects_invalid_settings() {
+        assert!(config("", &[("WORKFLOW_TIME_ZONE", "Mars/Olympus")]).is_err());
+        assert!(config("time_zone = \"Mars/Olympus\"", &[]).is_err());
+        assert!(config("", &[("WORKFLOW_DATE_FORMAT", "%Y-%")]).is_err());
+        assert!(config("", &[("WORKFLOW_ONE_ACTIVE_TASK", "ture")]).is_err());
+        assert!(config("", &[("WORKFLOW_POMODORO_ROUNDS", "four")]).is_err());
+        assert!(config("", &[("WORKFLOW_POMODORO_ROUNDS", "0")]).is_err());
+        assert!(config("[pomodoro]\nrounds = 0", &[]).is_err());
+        assert!(config("", &[("WORKFLOW_POMODORO_PAUSE", "5 minutes")]).is_err());
+        assert!(config("[pomodoro]\nlong_break = \"00:75:00\"", &[]).is_err());
+        assert!(config("", &[("WORKFLOW_POMODORO_WORK", "00:00:00")]).is_err());
+        assert!(config("[pomodoro]\npause = \"00:00:00\"", &[]).is_err());
+    }
+
+    #[test]
+    fn parses_lengths() {
+        assert_eq!(parse_length(None, "00:25:00"), Ok(time(0, 25)));
+        assert_eq!(
+            parse_length(Some("01:30:00".to_string()), "00:25:00"),
+            Ok(time(1, 30))
+        );
+        assert!(parse_length(Some("90".to_string()), "00:25:00").is_err());
+        assert!(parse_length(Some("00:30".to_string()), "00:25:00").is_err());
+        assert!(parse_length(Some("00:00:00".to_string()), "00:25:00").is_err());
+        assert_eq!(
+            parse_length(Some("00:00:01".to_string()), "00:25:00"),
+            Ok(NaiveTime::from_hms_opt(0, 0, 1).unwrap())
+        );
+    }
+}
@@ -0,0 +1,333 @@
+//! Work and pause intervals of a task, computed from its logs.
+//!
+//! A task works from a `B` or `R` log until the next log and pauses from a `P` log until the
+//! next one; nothing is counted after an `E` log. The interval after the last log of a task that
+//! hasn't ended is open and only counted up to a given time, usually now. Stats of a range only
+//! count the parts of the intervals within it, so stats of adjacent ranges add up to the stats of
+//! the whole history.
+
+use std::ops::Range;
+
+use chrono::{Duration, NaiveDateTime};
+
+use crate::models::LogType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntervalKind {
+    Work,
+    Pause,
+}
+
+/// Time between two consecutive logs of a task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interval {
+    pub kind: IntervalKind,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+impl Interval {
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+
+    /// Returns the part of the interval within `range`, which is empty if they don't overlap.
+    pub fn clip(&self, range: &Range<NaiveDateTime>) -> Interval {
+        Interval {
+            kind: self.kind,
+            start: self.start.clamp(range.start, range.end),
+            end: self.end.clamp(range.start, range.end),
+        }
+    }
+}
+
+/// Aggregates of a task's intervals.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Summary {
+    /// Time from the first log to the end of the last interval, or to the last log.
+    pub total_time: Duration,
+    pub total_worked: Duration,
+    pub longest_work: Duration,
+    pub longest_pause: Duration,
+    pub pause_num: i32,
+    /// Whether the task has been ended.
+    pub completed: bool,
+    /// Number of logs counted.
+    pub log_num: usize,
+}
+
+impl Summary {
+    /// Whether the task was logged or worked on at all.
+    pub fn is_active(&self) -> bool {
+        self.log_num > 0 || !self.total_worked.is_zero()
+    }
+}
+
+fn kind_after(log_type: LogType) -> Option<IntervalKind> {
+    match log_type {
+        LogType::Begin | LogType::Resume => Some(IntervalKind::Work),
+        LogType::Pause => Some(IntervalKind::Pause),
+        LogType::End => None,
+    }
+}
+
+/// Splits a task's logs, ordered by date, into the intervals between them. When `until` is given
+/// and the task hasn't ended, the open interval after the last log is included up to `until`.
+pub fn intervals(logs: &[(LogType, NaiveDateTime)], until: Option<NaiveDateTime>) -> Vec<Interval> {
+    let mut intervals: Vec<Interval> = logs
+        .windows(2)
+        .filter_map(|pair| {
+            let ((previous_type, start), (_, end)) = (pair[0], pair[1]);
+            let kind = kind_after(previous_type)?;
+            Some(Interval { kind, start, end })
+        })
+        .collect();
+
+    if let (Some(&(log_type, start)), Some(until)) = (logs.last(), until) {
+        if let Some(kind) = kind_after(log_type).filter(|_| until > start) {
+            intervals.push(Interval {
+                kind,
+                start,
+                end: until,
+            });
+        }
+    }
+    intervals
+}
+
+/// Aggregates a task's logs, ordered by date, with the open interval counted up to `until` when
+/// given. When `range` is given, only the logs and the parts of intervals within it are counted.
+pub fn summarize(
+    logs: &[(LogType, NaiveDateTime)],
+    range: Option<&Range<NaiveDateTime>>,
+    until: Option<NaiveDateTime>,
+) -> Summary {
+    let in_range = |date: &NaiveDateTime| range.is_none_or(|range| range.contains(date));
+    let clip = |date: NaiveDateTime| range.map_or(date, |range| date.clamp(range.start, range.end));
+
+    let intervals = intervals(logs, until);
+    let mut summary = Summary::default();
+    for interval in intervals.iter() {
+        let interval = match range {
+            Some(range) => interval.clip(range),
+            None => interval.clone(),
+        };
+        match interval.kind {
+            IntervalKind::Work => {
+                summary.total_worked += interval.duration();
+                summary.longest_work = summary.longest_work.max(interval.duration());
+            }
+            IntervalKind::Pause => {
+                summary.longest_pause = summary.longest_pause.max(interval.duration());
+            }
+        }
+    }
+
+    let counted: Vec<&(LogType, NaiveDateTime)> =
+        logs.iter().filter(|(_, date)| in_range(date)).collect();
+    summary.log_num = counted.len();
+    summary.pause_num = counted
+        .iter()
+        .filter(|(log_type, _)| *log_type == LogType::Pause)
+        .count() as i32;
+    summary.completed = logs
+        .last()
+        .is_some_and(|(log_type, date)| *log_type == LogType::End && in_range(date));
+    if let (Some((_, first)), Some((_, last))) = (logs.first(), logs.last()) {
+        let last = intervals
+            .last()
+            .map_or(*last, |interval| interval.end.max(*last));
+        summary.total_time = clip(last) - clip(*first);
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use proptest::prelude::*;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    fn day(day: u32) -> Range<NaiveDateTime> {
+        at(day, 0, 0)..at(day + 1, 0, 0)
+    }
+
+    #[test]
+    fn splits_logs_into_work_and_pause() {
+        let logs = [
+            (LogType::Begin, at(1, 9, 0)),
+            (LogType::Pause, at(1, 10, 0)),
+            (LogType::Resume, at(1, 10, 15)),
+            (LogType::End, at(1, 11, 0)),
+        ];
+
+        let kinds: Vec<IntervalKind> = intervals(&logs, None).iter().map(|x| x.kind).collect();
+        assert_eq!(
+            kinds,
+            [IntervalKind::Work, IntervalKind::Pause, IntervalKind::Work]
+        );
+
+        let summary = summarize(&logs, None, None);
+        assert_eq!(summary.total_worked, Duration::minutes(105));
+        assert_eq!(summary.total_time, Duration::minutes(120));
+        assert_eq!(summary.longest_work, Duration::minutes(60));
+        assert_eq!(summary.longest_pause, Duration::minutes(15));
+        assert_eq!(summary.pause_num, 1);
+        assert!(summary.completed);
+    }
+
+    #[test]
+    fn ending_during_pause_counts_no_work() {
+        let logs = [
+            (LogType::Begin, at(1, 9, 0)),
+            (LogType::Pause, at(1, 10, 0)),
+            (LogType::End, at(1, 12, 0)),
+        ];
+
+        let summary = summarize(&logs, None, None);
+        assert_eq!(summary.total_worked, Duration::hours(1));
+        assert_eq!(summary.longest_pause, Duration::hours(2));
+    }
+
+    #[test]
+    fn open_interval_is_counted_only_until_given_time() {
+        let logs = [(LogType::Begin, at(1, 9, 0))];
+
+        let summary = summarize(&logs, None, None);
+        assert_eq!(summary.total_worked, Duration::zero());
+        assert_eq!(summary.log_num, 1);
+        assert!(!summary.completed);
+
+        let summary = summarize(&logs, None, Some(at(1, 11, 30)));
+        assert_eq!(summary.total_worked, Duration::minutes(150));
+        assert_eq!(summary.longest_work, Duration::minutes(150));
+        assert_eq!(summary.total_time, Duration::minutes(150));
+    }
+
+    #[test]
+    fn open_pause_and_ended_tasks() {
+        let paused = [
+            (LogType::Begin, at(1, 9, 0)),
+            (LogType::Pause, at(1, 10, 0)),
+        ];
+        let summary = summarize(&paused, None, Some(at(1, 12, 0)));
+        assert_eq!(summary.total_worked, Duration::hours(1));
+        assert_eq!(summary.longest_pause, Duration::hours(2));
+
+        let ended = [(LogType::Begin, at(1, 9, 0)), (LogType::End, at(1, 10, 0))];
+        assert_eq!(
+            summarize(&ended, None, Some(at(1, 12, 0))),
+            summarize(&ended, None, None)
+        );
+        assert_eq!(intervals(&ended, Some(at(1, 8, 0))).len(), 1);
+    }
+
+    #[test]
+    fn no_logs_give_empty_summary() {
+        let summary = summarize(&[], None, None);
+        assert_eq!(summary, Summary::default());
+        assert!(!summary.is_active());
+    }
+
+    #[test]
+    fn clips_work_crossing_midnight() {
+        let logs = [
+            (LogType::Begin, at(1, 23, 0)),
+            (LogType::Pause, at(2, 1, 0)),
+        ];
+
+        let first = summarize(&logs, Some(&day(1)), None);
+        let second = summarize(&logs, Some(&day(2)), None);
+        assert_eq!(first.total_worked, Duration::hours(1));
+        assert_eq!(first.pause_num, 0);
+        assert_eq!(second.total_worked, Duration::hours(1));
+        assert_eq!(second.pause_num, 1);
+        assert!(!summarize(&logs, Some(&day(3)), None).is_active());
+    }
+
+    #[test]
+    fn work_spanning_the_range_is_active_without_logs() {
+        let logs = [(LogType::Begin, at(1, 12, 0)), (LogType::End, at(3, 12, 0))];
+
+        let summary = summarize(&logs, Some(&day(2)), None);
+        assert_eq!(summary.log_num, 0);
+        assert_eq!(summary.total_worked, Duration::hours(24));
+        assert!(summary.is_active());
+        assert!(!summary.completed);
+    }
+
+    /// Logs in an allowed order, a number of minutes apart, starting on the 1st at midnight.
+    fn log_sequence() -> impl Strategy<Value = Vec<(LogType, NaiveDateTime)>> {
+        (prop::collection::vec(0i64..2000, 0..20), any::<bool>()).prop_map(|(gaps, ended)| {
+            let mut date = at(1, 0, 0);
+            let mut logs: Vec<(LogType, NaiveDateTime)> = vec![];
+            for gap in gaps {
+                date += Duration::minutes(gap);
+                let log_type = match logs.last() {
+                    None => LogType::Begin,
+                    Some((LogType::Pause, _)) => LogType::Resume,
+                    Some(_) => LogType::Pause,
+                };
+                logs.push((log_type, date));
+            }
+            if ended && logs.len() > 1 {
+                logs.last_mut().unwrap().0 = LogType::End;
+            }
+            logs
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn days_add_up_to_the_whole_history(logs in log_sequence(), open in 0i64..3000) {
+            let until = logs.last().map(|(_, date)| *date + Duration::minutes(open));
+            let whole = summarize(&logs, None, until);
+            let days: Vec<Summary> =
+                (1..=30).map(|x| summarize(&logs, Some(&day(x)), until)).collect();
+
+            let worked = days.iter().fold(Duration::zero(), |total, x| total + x.total_worked);
+            let pauses: i32 = days.iter().map(|x| x.pause_num).sum();
+            let total_time = days.iter().fold(Duration::zero(), |total, x| total + x.total_time);
+            let completed = days.iter().filter(|x| x.completed).count();
+            prop_assert_eq!(worked, whole.total_worked);
+            prop_assert_eq!(pauses, whole.pause_num);
+            prop_assert_eq!(total_time, whole.total_time);
+            prop_assert_eq!(completed, usize::from(whole.completed));
+            prop_assert_eq!(days.iter().map(|x| x.log_num).sum::<usize>(), logs.len());
+        }
+
+        #[test]
+        fn work_and_pauses_fill_the_history(logs in log_sequence()) {
+            let summary = summarize(&logs, None, None);
+            let paused = intervals(&logs, None)
+                .iter()
+                .filter(|x| x.kind == IntervalKind::Pause)
+                .fold(Duration::zero(), |total, x| total + x.duration());
+
+            prop_assert_eq!(summary.total_worked + paused, summary.total_time);
+            prop_assert!(summary.longest_work <= summary.total_worked);
+            prop_assert!(summary.longest_pause <= paused);
+        }
+
+        #[test]
+        fn clipped_summary_never_exceeds_the_whole(logs in log_sequence(), first in 1u32..28, len in 0u32..3) {
+            let range = at(first, 0, 0)..at(first + len, 12, 0);
+            let whole = summarize(&logs, None, None);
+            let clipped = summarize(&logs, Some(&range), None);
+
+            prop_assert!(clipped.total_worked <= whole.total_worked);
+            prop_assert!(clipped.longest_work <= whole.longest_work);
+            prop_assert!(clipped.longest_pause <= whole.longest_pause);
+            prop_assert!(clipped.pause_num <= whole.pause_num);
+            prop_assert!(clipped.total_time <= whole.total_time);
+        }
+    }
+}
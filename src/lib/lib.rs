@@ -1,24 +1,67 @@
+use diesel::connection::SimpleConnection;
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
-use dotenvy::dotenv;
-use std::env;
-use chrono::Local;
+use diesel::sqlite::SqliteConnection;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use std::error::Error;
+use chrono::NaiveDateTime;
 use crate::models::*;
 
+pub mod config;
+pub mod intervals;
+pub mod lookup;
 pub mod models;
 pub mod schema;
 
-pub fn establish_connection() -> PgConnection {
-    dotenv().ok();
+/// Storage backend used by every query in the app.
+///
+/// The variant is chosen by the scheme of `DATABASE_URL`: `postgres://` and `postgresql://`
+/// connect to a PostgreSQL server, `sqlite://PATH` (or a bare file path) opens a local
+/// SQLite database file.
+#[derive(diesel::MultiConnection)]
+pub enum DbConnection {
+    Postgresql(PgConnection),
+    Sqlite(SqliteConnection),
+}
+
+pub fn establish_connection() -> DbConnection {
+    let database_url = config::get()
+        .database_url
+        .as_deref()
+        .expect("DATABASE_URL must be set, in the environment or as database_url in the config file");
+    connect(database_url).unwrap_or_else(|_| panic!("Error connecting to {}", database_url))
+}
+
+fn connect(database_url: &str) -> ConnectionResult<DbConnection> {
+    if database_url.starts_with("postgres://") || database_url.starts_with("postgresql://") {
+        return PgConnection::establish(database_url).map(DbConnection::Postgresql);
+    }
+
+    let path = database_url.strip_prefix("sqlite://").unwrap_or(database_url);
+    let mut conn = SqliteConnection::establish(path)?;
+    // SQLite leaves foreign keys unchecked unless asked, and "No such task" relies on them
+    conn.batch_execute("PRAGMA foreign_keys = ON; PRAGMA busy_timeout = 5000;")
+        .map_err(ConnectionError::CouldntSetupConfiguration)?;
+
+    Ok(DbConnection::Sqlite(conn))
+}
+
+pub const PG_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/postgres");
+pub const SQLITE_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/sqlite");
 
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    PgConnection::establish(&database_url)
-        .unwrap_or_else(|_| panic!("Error connecting to {}", database_url))
+/// Applies every embedded migration the database hasn't seen yet and returns their versions.
+pub fn run_migrations(conn: &mut DbConnection) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+    let applied = match conn {
+        DbConnection::Postgresql(conn) => conn.run_pending_migrations(PG_MIGRATIONS)?,
+        DbConnection::Sqlite(conn) => conn.run_pending_migrations(SQLITE_MIGRATIONS)?,
+    };
+
+    Ok(applied.iter().map(|version| version.to_string()).collect())
 }
 
 use self::models::{NewApp, App};
 
-    pub fn create_app(conn: &mut PgConnection, app_name: &str) ->Result<App,diesel::result::Error>{
+    pub fn create_app(conn: &mut DbConnection, app_name: &str) ->Result<App,diesel::result::Error>{
         use crate::schema::apps;
 
         let new_app = NewApp { app_name };
@@ -26,7 +69,6 @@ use self::models::{NewApp, App};
 
         diesel::insert_into(apps::table)
         .values(&new_app)
-        .returning(App::as_returning())
         .get_result(conn)
 
     // match app {
@@ -38,56 +80,61 @@ use self::models::{NewApp, App};
 
     use self::models::{NewTask,Task};
 
-    pub fn create_task(conn: &mut PgConnection,project_id: i32 , task_name: &str, _planned_time: Option<&str>)-> Result<Task,diesel::result::Error>{
+    pub fn create_task(conn: &mut DbConnection,project_id: i32 , task_name: &str, planned_minutes: Option<i32>)-> Result<Task,diesel::result::Error>{
         use crate::schema::tasks;
 
-        let new_task=NewTask{project_id, task_name,username: &whoami::username(),planned_time:_planned_time};
+        let new_task=NewTask{project_id, task_name,username: &config::get().username,planned_minutes};
 
         diesel::insert_into(tasks::table)
             .values(&new_task)
-            .returning(Task::as_returning())
             .get_result(conn)
             
     }
-    pub fn create_project(conn: &mut PgConnection,project_name: &str, _planned_time: Option<&str>)-> Result<Project,diesel::result::Error>{
+    pub fn create_project(conn: &mut DbConnection,project_name: &str, planned_minutes: Option<i32>)-> Result<Project,diesel::result::Error>{
         use crate::schema::projects;
 
 
-        let new_project=NewProject{project_name,username: &whoami::username(),planned_time:_planned_time};
+        let new_project=NewProject{project_name,username: &config::get().username,planned_minutes};
 
         diesel::insert_into(projects::table)
             .values(&new_project)
-            .returning(Project::as_returning())
             .get_result(conn)
             
     }
     use self::models::{NewProjectApp,ProjectApp};
 
-    pub fn create_app_detail(conn: &mut PgConnection, task_id: i32, app_id:i32)-> ProjectApp{
-        
+    pub fn create_app_detail(conn: &mut DbConnection, project_id: i32, app_id:i32)-> Result<ProjectApp,diesel::result::Error>{
+        use crate::schema::project_apps;
 
-        let new_app_detail=NewProjectApp{project_id: task_id,app_id};
+        let new_app_detail=NewProjectApp{project_id,app_id};
 
-        diesel::insert_into(schema::project_apps::table)
+        diesel::insert_into(project_apps::table)
+            .values(&new_app_detail)
+            .get_result(conn)
+    }
+
+    use self::models::{NewTaskApp,TaskApp};
+
+    pub fn create_task_app_detail(conn: &mut DbConnection, task_id: i32, app_id:i32)-> Result<TaskApp,diesel::result::Error>{
+        use crate::schema::task_apps;
+
+        let new_app_detail=NewTaskApp{task_id,app_id};
+
+        diesel::insert_into(task_apps::table)
             .values(&new_app_detail)
-            .returning(ProjectApp::as_returning())
             .get_result(conn)
-            .expect("Error creating new task")
     }
 
 
     use self::models::{NewLog,Log};
 
-    pub fn create_log(conn: &mut PgConnection, task_id: i32, log_type:String)-> Result<Log,diesel::result::Error>{
+    pub fn create_log(conn: &mut DbConnection, task_id: i32, log_type: LogType, date: NaiveDateTime)-> Result<Log,diesel::result::Error>{
         use crate::schema::log;
 
-        let current_local_time = Local::now().naive_local();
-
-        let new_log=NewLog{task_id,log_type,date: current_local_time};
+        let new_log=NewLog{task_id,log_type,date};
 
         diesel::insert_into(log::table)
             .values(&new_log)
-            .returning(Log::as_returning())
             .get_result(conn)
 
     }
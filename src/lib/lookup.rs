@@ -0,0 +1,163 @@
+//! Finding tasks and projects by the names typed in commands.
+//!
+//! A name matches an item named exactly like it, or else one whose name differs only in case.
+//! When several items match the same way the name is ambiguous; when none does, the items with
+//! similar names are suggested instead.
+
+/// Result of looking a name up among items.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Match<T> {
+    One(T),
+    /// Every item the name matches.
+    Ambiguous(Vec<T>),
+    /// No item matches; holds the items with similar names, the most similar first.
+    NoMatch(Vec<T>),
+}
+
+/// Number of suggestions given at most when no item matches.
+const SUGGESTIONS: usize = 5;
+
+/// Looks `query` up among `items`, named by `name`.
+pub fn find<T>(query: &str, items: Vec<T>, name: impl Fn(&T) -> &str) -> Match<T> {
+    let (exact, rest): (Vec<T>, Vec<T>) = items.into_iter().partition(|x| name(x) == query);
+    if !exact.is_empty() {
+        return one_or_ambiguous(exact);
+    }
+
+    let query = query.to_lowercase();
+    let (folded, rest): (Vec<T>, Vec<T>) = rest
+        .into_iter()
+        .partition(|x| name(x).to_lowercase() == query);
+    if !folded.is_empty() {
+        return one_or_ambiguous(folded);
+    }
+
+    let max_distance = (query.chars().count() / 3).max(1);
+    let mut similar: Vec<(usize, T)> = rest
+        .into_iter()
+        .filter_map(|x| {
+            let candidate = name(&x).to_lowercase();
+            let distance = distance(&query, &candidate);
+            let contains = query.chars().count() > 1
+                && (candidate.contains(&query) || query.contains(&candidate));
+            (distance <= max_distance || contains).then_some((distance, x))
+        })
+        .collect();
+    similar.sort_by_key(|(distance, _)| *distance);
+    Match::NoMatch(
+        similar
+            .into_iter()
+            .take(SUGGESTIONS)
+            .map(|(_, x)| x)
+            .collect(),
+    )
+}
+
+fn one_or_ambiguous<T>(mut items: Vec<T>) -> Match<T> {
+    if items.len() == 1 {
+        Match::One(items.remove(0))
+    } else {
+        Match::Ambiguous(items)
+    }
+}
+
+/// Levenshtein distance: the number of characters inserted, deleted or replaced to turn `a`
+/// into `b`.
+pub fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, y) in b.iter().enumerate() {
+            let replaced = previous[j] + usize::from(x != *y);
+            current.push(replaced.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find_name<'a>(query: &str, names: &[&'a str]) -> Match<&'a str> {
+        find(query, names.to_vec(), |x| x)
+    }
+
+    #[test]
+    fn exact_match_wins_over_case_insensitive_ones() {
+        assert_eq!(
+            find_name("Report", &["report", "Report", "REPORT"]),
+            Match::One("Report")
+        );
+    }
+
+    #[test]
+    fn matches_regardless_of_case() {
+        assert_eq!(
+            find_name("report", &["Report", "review"]),
+            Match::One("Report")
+        );
+    }
+
+    #[test]
+    fn several_matches_are_ambiguous() {
+        assert_eq!(
+            find_name("report", &["Report", "review", "REPORT"]),
+            Match::Ambiguous(vec!["Report", "REPORT"])
+        );
+    }
+
+    #[test]
+    fn suggests_similar_names_most_similar_first() {
+        assert_eq!(
+            find_name("repor", &["review", "reports", "report", "backup"]),
+            Match::NoMatch(vec!["report", "reports"])
+        );
+        assert_eq!(
+            find_name("port", &["report", "backup"]),
+            Match::NoMatch(vec!["report"])
+        );
+    }
+
+    #[test]
+    fn suggests_names_only_within_a_third_of_the_length() {
+        // Two edits away from a six letter name, one edit away from a short one.
+        assert_eq!(
+            find_name("rexorx", &["report"]),
+            Match::NoMatch(vec!["report"])
+        );
+        assert_eq!(
+            find_name("rxxort", &["report"]),
+            Match::NoMatch(vec!["report"])
+        );
+        assert_eq!(find_name("rxxxrt", &["report"]), Match::NoMatch(vec![]));
+        assert_eq!(find_name("ab", &["ac", "cd"]), Match::NoMatch(vec!["ac"]));
+        assert_eq!(
+            find_name("a", &["ab", "b", "abc"]),
+            Match::NoMatch(vec!["ab", "b"])
+        );
+    }
+
+    #[test]
+    fn suggests_at_most_five_names() {
+        let names = [
+            "tasks", "task1", "task2", "task3", "task4", "task5", "task6",
+        ];
+        match find_name("task", &names) {
+            Match::NoMatch(x) => assert_eq!(x.len(), SUGGESTIONS),
+            x => panic!("unexpected {:?}", x),
+        }
+    }
+
+    #[test]
+    fn counts_edits_between_names() {
+        assert_eq!(distance("", ""), 0);
+        assert_eq!(distance("report", "report"), 0);
+        assert_eq!(distance("", "abc"), 3);
+        assert_eq!(distance("report", "reprot"), 2);
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("żółw", "zolw"), 3);
+    }
+}
@@ -1,26 +1,36 @@
+use diesel::backend::Backend;
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
 use diesel::prelude::*;
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::Text;
 use chrono::NaiveDateTime;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Queryable, Selectable, Debug,Clone)]
+#[derive(Queryable, Selectable, Serialize, Debug,Clone)]
 #[diesel(table_name = crate::schema::projects)]
 pub struct Project {
     pub project_id: i32,
     pub project_name: String,
     pub username: String,
-    pub planned_time: Option<String>
+    pub planned_minutes: Option<i32>,
+    pub archived: bool,
 }
 
-#[derive(Queryable, Selectable, Debug,Clone)]
+#[derive(Queryable, Selectable, Serialize, Debug,Clone)]
 #[diesel(table_name = crate::schema::tasks)]
 pub struct Task {
     pub task_id: i32,
     pub project_id:i32,
     pub task_name: String,
     pub username: String,
-    pub planned_time: Option<String>
+    pub planned_minutes: Option<i32>,
+    pub archived: bool,
 }
 
-#[derive(Queryable, Selectable, Debug,Clone)]
+#[derive(Queryable, Selectable, Serialize, Debug,Clone)]
 #[diesel(table_name = crate::schema::apps)]
 pub struct App {
     pub app_id: i32,
@@ -35,15 +45,188 @@ pub struct ProjectApp {
     pub app_id: i32,
 }
 
-#[derive(Queryable, Selectable, Debug, Clone)]
+#[derive(Queryable, Selectable, Debug,Clone)]
+#[diesel(table_name = crate::schema::task_apps)]
+pub struct TaskApp {
+    pub id: i32,
+    pub task_id: i32,
+    pub app_id: i32,
+}
+
+/// Kind of a log entry, stored in the `log_type` column as a single letter.
+#[derive(AsExpression, FromSqlRow, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[diesel(sql_type = Text)]
+pub enum LogType {
+    Begin,
+    Pause,
+    Resume,
+    End,
+}
+
+impl LogType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogType::Begin => "B",
+            LogType::Pause => "P",
+            LogType::Resume => "R",
+            LogType::End => "E",
+        }
+    }
+}
+
+impl fmt::Display for LogType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Serialize for LogType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl FromStr for LogType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "B" => Ok(LogType::Begin),
+            "P" => Ok(LogType::Pause),
+            "R" => Ok(LogType::Resume),
+            "E" => Ok(LogType::End),
+            _ => Err(format!("Unknown log type '{}'", s)),
+        }
+    }
+}
+
+impl<DB> ToSql<Text, DB> for LogType
+where
+    DB: Backend,
+    str: ToSql<Text, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        self.as_str().to_sql(out)
+    }
+}
+
+impl<DB> FromSql<Text, DB> for LogType
+where
+    DB: Backend,
+    String: FromSql<Text, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+        Ok(String::from_sql(bytes)?.parse()?)
+    }
+}
+
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
 #[diesel(table_name = crate::schema::log)]
 pub struct Log {
     pub log_id: i32,
     pub task_id: i32,
-    pub log_type: String,
+    pub log_type: LogType,
     pub date: NaiveDateTime,
 }
 
+/// Kind of a pomodoro session, stored in the `kind` column as `work` or `break`.
+#[derive(AsExpression, FromSqlRow, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[diesel(sql_type = Text)]
+pub enum PomodoroKind {
+    Work,
+    Break,
+}
+
+impl PomodoroKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PomodoroKind::Work => "work",
+            PomodoroKind::Break => "break",
+        }
+    }
+}
+
+impl fmt::Display for PomodoroKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Serialize for PomodoroKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl FromStr for PomodoroKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "work" => Ok(PomodoroKind::Work),
+            "break" => Ok(PomodoroKind::Break),
+            _ => Err(format!("Unknown pomodoro kind '{}'", s)),
+        }
+    }
+}
+
+impl<DB> ToSql<Text, DB> for PomodoroKind
+where
+    DB: Backend,
+    str: ToSql<Text, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        self.as_str().to_sql(out)
+    }
+}
+
+impl<DB> FromSql<Text, DB> for PomodoroKind
+where
+    DB: Backend,
+    String: FromSql<Text, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+        Ok(String::from_sql(bytes)?.parse()?)
+    }
+}
+
+/// A work or a break counted down in pomodoro mode, `completed` unless it was interrupted.
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::schema::pomodoro_sessions)]
+pub struct PomodoroSession {
+    pub session_id: i32,
+    pub task_id: i32,
+    pub kind: PomodoroKind,
+    pub started_at: NaiveDateTime,
+    pub planned_seconds: i32,
+    pub actual_seconds: i32,
+    pub completed: bool,
+}
+
+/// The clock of a user's pomodoro mode, kept up to date while it runs so that the mode can be
+/// resumed after its terminal is closed. `deadline` is missing while the clock is stopped, and
+/// the cycle lengths when there is no cycle.
+#[derive(Queryable, Selectable, Insertable, AsChangeset, Debug, Clone)]
+#[diesel(table_name = crate::schema::pomodoro_timers, treat_none_as_null = true)]
+pub struct PomodoroTimer {
+    pub username: String,
+    /// The terminal running the mode, as `host:pid`.
+    pub owner: String,
+    pub task_id: i32,
+    pub kind: PomodoroKind,
+    pub title: String,
+    pub started_at: NaiveDateTime,
+    pub planned_seconds: i32,
+    pub deadline: Option<NaiveDateTime>,
+    pub remaining_seconds: i32,
+    pub round: i32,
+    pub work_seconds: Option<i32>,
+    pub break_seconds: Option<i32>,
+    pub long_break_seconds: Option<i32>,
+    pub rounds: Option<i32>,
+    pub updated_at: NaiveDateTime,
+}
+
 use crate::schema::apps;
 
 #[derive(Insertable)]
@@ -58,7 +241,7 @@ pub struct NewApp<'a> {
 pub struct NewProject<'a>{
     pub project_name: &'a str,
     pub username: &'a str,
-    pub planned_time:  Option<&'a str>
+    pub planned_minutes: Option<i32>
 }
 
 
@@ -68,7 +251,26 @@ pub struct NewTask<'a>{
     pub project_id: i32,
     pub task_name: &'a str,
     pub username: &'a str,
-    pub planned_time:  Option<&'a str>
+    pub planned_minutes: Option<i32>
+}
+
+/// Changes to a project, fields left `None` are not updated.
+#[derive(AsChangeset,Default)]
+#[diesel(table_name = crate::schema::projects)]
+pub struct ProjectChanges<'a>{
+    pub project_name: Option<&'a str>,
+    pub planned_minutes: Option<i32>,
+    pub archived: Option<bool>,
+}
+
+/// Changes to a task, fields left `None` are not updated.
+#[derive(AsChangeset,Default)]
+#[diesel(table_name = crate::schema::tasks)]
+pub struct TaskChanges<'a>{
+    pub project_id: Option<i32>,
+    pub task_name: Option<&'a str>,
+    pub planned_minutes: Option<i32>,
+    pub archived: Option<bool>,
 }
 
 #[derive(Insertable,PartialEq)]
@@ -78,10 +280,27 @@ pub struct NewProjectApp{
     pub app_id: i32,
 }
 
+#[derive(Insertable,PartialEq)]
+#[diesel(table_name = crate::schema::task_apps)]
+pub struct NewTaskApp{
+    pub task_id: i32,
+    pub app_id: i32,
+}
+
 #[derive(Insertable,PartialEq)]
 #[diesel(table_name = crate::schema::log)]
 pub struct NewLog{
     pub task_id: i32,
-    pub log_type: String,
+    pub log_type: LogType,
     pub date: NaiveDateTime
-}
\ No newline at end of file
+}
+#[derive(Insertable,PartialEq,Debug)]
+#[diesel(table_name = crate::schema::pomodoro_sessions)]
+pub struct NewPomodoroSession{
+    pub task_id: i32,
+    pub kind: PomodoroKind,
+    pub started_at: NaiveDateTime,
+    pub planned_seconds: i32,
+    pub actual_seconds: i32,
+    pub completed: bool,
+}
@@ -17,6 +17,40 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    pomodoro_sessions (session_id) {
+        session_id -> Int4,
+        task_id -> Int4,
+        #[max_length = 5]
+        kind -> Varchar,
+        started_at -> Timestamp,
+        planned_seconds -> Int4,
+        actual_seconds -> Int4,
+        completed -> Bool,
+    }
+}
+
+diesel::table! {
+    pomodoro_timers (username) {
+        username -> Varchar,
+        owner -> Varchar,
+        task_id -> Int4,
+        #[max_length = 5]
+        kind -> Varchar,
+        title -> Varchar,
+        started_at -> Timestamp,
+        planned_seconds -> Int4,
+        deadline -> Nullable<Timestamp>,
+        remaining_seconds -> Int4,
+        round -> Int4,
+        work_seconds -> Nullable<Int4>,
+        break_seconds -> Nullable<Int4>,
+        long_break_seconds -> Nullable<Int4>,
+        rounds -> Nullable<Int4>,
+        updated_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     project_apps (id) {
         id -> Int4,
@@ -32,8 +66,16 @@ diesel::table! {
         project_name -> Varchar,
         #[max_length = 20]
         username -> Varchar,
-        #[max_length = 20]
-        planned_time -> Nullable<Varchar>,
+        planned_minutes -> Nullable<Int4>,
+        archived -> Bool,
+    }
+}
+
+diesel::table! {
+    task_apps (id) {
+        id -> Int4,
+        task_id -> Int4,
+        app_id -> Int4,
     }
 }
 
@@ -45,20 +87,27 @@ diesel::table! {
         task_name -> Varchar,
         #[max_length = 20]
         username -> Varchar,
-        #[max_length = 20]
-        planned_time -> Nullable<Varchar>,
+        planned_minutes -> Nullable<Int4>,
+        archived -> Bool,
     }
 }
 
 diesel::joinable!(log -> tasks (task_id));
+diesel::joinable!(pomodoro_sessions -> tasks (task_id));
+diesel::joinable!(pomodoro_timers -> tasks (task_id));
 diesel::joinable!(project_apps -> apps (app_id));
 diesel::joinable!(project_apps -> projects (project_id));
+diesel::joinable!(task_apps -> apps (app_id));
+diesel::joinable!(task_apps -> tasks (task_id));
 diesel::joinable!(tasks -> projects (project_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     apps,
     log,
+    pomodoro_sessions,
+    pomodoro_timers,
     project_apps,
     projects,
+    task_apps,
     tasks,
 );
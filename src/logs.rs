@@ -1,22 +1,22 @@
-use crate::Commands;
-use crate::{db_operations, stats};
-use chrono::Local;
+use crate::db_operations::logs::LogError;
+use crate::output::{self, Format};
+use crate::{db_operations, stats, tasks};
+use chrono::{Duration, NaiveDateTime};
 use comfy_table::modifiers::UTF8_ROUND_CORNERS;
 use comfy_table::presets::UTF8_FULL;
 use comfy_table::*;
-use std::process;
-
-pub fn display_logs(args: &[String]) {
-    let a = if args.len() > 0 && (args[0] == "-t" || args[0] == "-tasks") {
-        db_operations::logs::get_logs(&args[1..])
-    } else if args.len() == 0 {
-        db_operations::logs::get_logs(&[])
-    } else {
-        println!("No such option!");
-        return;
-    };
+use workflow::config;
+use workflow::models::{Log, LogType};
+use workflow::DbConnection;
+
+pub fn display_logs(task_ids: &[i32], format: Format, connection: &mut DbConnection) {
+    let a = db_operations::logs::get_logs(task_ids, connection);
 
     if let Ok(x) = a {
+        if format != Format::Table {
+            return output::print_rows(&x, format);
+        }
+
         let mut table = Table::new();
         table
             .load_preset(UTF8_FULL)
@@ -41,7 +41,7 @@ pub fn display_logs(args: &[String]) {
                 Cell::new(row.log_id).set_alignment(CellAlignment::Center),
                 Cell::new(row.task_id).set_alignment(CellAlignment::Center),
                 Cell::new(row.log_type).set_alignment(CellAlignment::Center),
-                Cell::new(row.date.format("%Y-%m-%d %H:%M:%S").to_string())
+                Cell::new(row.date.format(&config::get().date_format).to_string())
                     .set_alignment(CellAlignment::Center),
             ]);
         }
@@ -49,119 +49,246 @@ pub fn display_logs(args: &[String]) {
     }
 }
 
-pub fn add_log(args: Vec<String>, log_type: Commands) {
-    if args.len() < 3 {
-        eprintln!("Too few args");
-        process::exit(-1);
+pub fn add_log(
+    task: &str,
+    log_type: LogType,
+    at: Option<NaiveDateTime>,
+    connection: &mut DbConnection,
+) {
+    match task.parse::<i32>() {
+        Ok(num) => {
+            let _ = add_log_by_id(log_type, &num, at, connection);
+        }
+        Err(_) => {
+            if let Some(task) = tasks::resolve_task(task, connection) {
+                let _ = add_log_by_id(log_type, &(task.task_id), at, connection);
+            }
+        }
     }
+}
 
-    if args.len() > 3 {
-        eprintln!("Too many args");
-        process::exit(-1);
+/// Parses a duration made of day, hour and minute parts, e.g. `2d`, `1h30m` or `45m`.
+pub fn parse_duration(input: &str) -> Option<Duration> {
+    let mut duration = Duration::zero();
+    let mut number = String::new();
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let value = number.parse::<i64>().ok()?;
+        duration += match c {
+            'd' => Duration::try_days(value),
+            'h' => Duration::try_hours(value),
+            'm' => Duration::try_minutes(value),
+            _ => None,
+        }?;
+        number.clear();
     }
-    let task_id = &args[2].parse::<i32>();
 
-    match task_id {
-        Ok(num) => {
-            add_log_by_id(log_type, num);
-        }
-        Err(_) => match db_operations::tasks::find_task(&args[2]) {
-            Ok(Some(task)) => {
-                add_log_by_id(log_type, &(task.task_id));
-            }
-            Ok(None) => println!("No such task!"),
-            Err(x) => println!("{}", x),
-        },
+    if input.is_empty() || !number.is_empty() {
+        return None;
     }
+    Some(duration)
 }
 
-pub fn add_log_by_id(log_type: Commands, num: &i32) -> usize {
-    let recent_log = db_operations::logs::get_recent_log(*num, true);
+/// Parses a log time given either as an absolute date `YYYY-MM-DD HH:MM` or relatively to `now`
+/// as a negative offset made of days, hours and minutes, e.g. `-20m`, `-1h30m`, `-2d`. A time
+/// later than `now` is refused, as no log could be made now after it.
+pub fn parse_time(input: &str, now: NaiveDateTime) -> Result<NaiveDateTime, &'static str> {
+    const FORMAT_ERROR: &str =
+        "Time should be in format \"YYYY-MM-DD HH:MM\" or an offset like -20m, -1h30m, -2d";
 
-    match recent_log {
-        Err(x) => {
-            println!("{}", x);
-            return 0 as usize;
-        }
-        Ok(None) => {
-            if log_type != (Commands::Begin) {
-                println!("First begin the task, then perform other operations!");
-                return 0 as usize;
+    let offset = match input.trim().strip_prefix('-') {
+        Some(x) => x,
+        None => {
+            let time = NaiveDateTime::parse_from_str(input.trim(), "%Y-%m-%d %H:%M")
+                .map_err(|_| FORMAT_ERROR)?;
+            if time > now {
+                return Err("Time can't be in the future");
             }
+            return Ok(time);
         }
-        Ok(Some(x)) if x.log_type == Commands::End.to_string() => {
-            println!("Task has been ended!");
-            return 0 as usize;
+    };
+
+    let duration = parse_duration(offset).ok_or(FORMAT_ERROR)?;
+    now.checked_sub_signed(duration).ok_or(FORMAT_ERROR)
+}
+
+pub fn add_log_by_id(
+    log_type: LogType,
+    num: &i32,
+    at: Option<NaiveDateTime>,
+    connection: &mut DbConnection,
+) -> Result<usize, LogError> {
+    let date = at.unwrap_or_else(config::now);
+    let (recent_log, new_log, paused) = match db_operations::logs::add_log(
+        *num,
+        log_type,
+        date,
+        config::get().one_active_task,
+        connection,
+    ) {
+        Ok(x) => x,
+        Err(x) => {
+            println!("{}", x);
+            return Err(x);
         }
-        Ok(Some(x)) if log_type == Commands::End => {
-            let duration = Local::now().naive_local().signed_duration_since(x.date);
-            if x.log_type == Commands::Pause.to_string() {
-                print!("Ending pause that lasted: ");
-            } else {
-                print!("You've been working since last pause ");
-            }
-            println!(
-                "{} days, {} hours, {} minutes",
-                duration.num_days(),
-                duration.num_hours(),
-                duration.num_minutes()
-            );
-            let first_log = db_operations::logs::get_recent_log(*num, false);
-            match first_log {
-                Err(a) => {
-                    println!("{}", a);
+    };
+    print_paused(&paused);
+
+    if let Some(x) = recent_log {
+        let duration = new_log.date.signed_duration_since(x.date);
+        match log_type {
+            LogType::End => {
+                if x.log_type == LogType::Pause {
+                    print!("Ending pause that lasted: ");
+                } else {
+                    print!("You've been working since last pause ");
                 }
-                Ok(None) => println!("Error finding first log!"),
-                Ok(Some(a)) => {
-                    let duration = Local::now().naive_local().signed_duration_since(a.date);
-                    println!(
-                        "Total time spent on task:  {} days, {} hours, {} minutes",
-                        duration.num_days(),
-                        duration.num_hours(),
-                        duration.num_minutes()
-                    );
+                println!(
+                    "{} days, {} hours, {} minutes",
+                    duration.num_days(),
+                    duration.num_hours(),
+                    duration.num_minutes()
+                );
+                let first_log = db_operations::logs::get_recent_log(*num, false, connection);
+                match first_log {
+                    Err(a) => {
+                        println!("{}", a);
+                    }
+                    Ok(None) => println!("Error finding first log!"),
+                    Ok(Some(a)) => {
+                        let duration = new_log.date.signed_duration_since(a.date);
+                        println!(
+                            "Total time spent on task:  {} days, {} hours, {} minutes",
+                            duration.num_days(),
+                            duration.num_hours(),
+                            duration.num_minutes()
+                        );
+                    }
                 }
             }
+            LogType::Pause => {
+                println!(
+                    "You've been working {} days, {} hours, {} minutes",
+                    duration.num_days(),
+                    duration.num_hours(),
+                    duration.num_minutes()
+                );
+            }
+            LogType::Resume => {
+                println!(
+                    "Your pause was {} days, {} hours, {} minutes long",
+                    duration.num_days(),
+                    duration.num_hours(),
+                    duration.num_minutes()
+                );
+            }
+            LogType::Begin => (),
         }
-        Ok(Some(x))
-            if x.log_type == Commands::Pause.to_string() && log_type == (Commands::Pause) =>
-        {
-            println!("Task has already been paused");
-            return 0 as usize;
-        }
-        Ok(Some(x))
-            if x.log_type != Commands::Pause.to_string() && log_type == (Commands::Resume) =>
-        {
-            println!("Pause task before you resume it");
-            return 0 as usize;
-        }
-        Ok(Some(_)) if log_type == Commands::Begin => {
-            println!("Task has already been started");
-            return 0 as usize;
-        }
-        Ok(Some(x)) if log_type == Commands::Pause => {
-            let duration = Local::now().naive_local().signed_duration_since(x.date);
+    }
+
+    let stats = db_operations::stats::get_stats(&[], connection);
+    Ok(stats::display_content(
+        stats,
+        stats::PrintMode::ConcreteTasks,
+        Some(vec![*num]),
+        Format::Table,
+        connection,
+    ))
+}
+
+fn print_paused(paused: &[Log]) {
+    for x in paused {
+        println!(
+            "Paused task {} at {}",
+            x.task_id,
+            x.date.format(&config::get().date_format)
+        );
+    }
+}
+
+/// Begins or resumes the task and pauses the other running tasks.
+pub fn switch(task: &str, at: Option<NaiveDateTime>, connection: &mut DbConnection) {
+    let Some(task) = tasks::resolve_task(task, connection) else {
+        return;
+    };
+
+    let date = at.unwrap_or_else(config::now);
+    match db_operations::logs::switch_task(task.task_id, date, connection) {
+        Ok((_, new_log, paused)) => {
+            print_paused(&paused);
             println!(
-                "You've been working {} days, {} hours, {} minutes",
-                duration.num_days(),
-                duration.num_hours(),
-                duration.num_minutes()
+                "{} task {} \"{}\" at {}",
+                if new_log.log_type == LogType::Begin {
+                    "Began"
+                } else {
+                    "Resumed"
+                },
+                task.task_id,
+                task.task_name,
+                new_log.date.format(&config::get().date_format)
             );
-        }
-        Ok(Some(x)) if log_type == Commands::Resume => {
-            let duration = Local::now().naive_local().signed_duration_since(x.date);
-            println!(
-                "Your pause was {} days, {} hours, {} minutes long",
-                duration.num_days(),
-                duration.num_hours(),
-                duration.num_minutes()
+
+            let stats = db_operations::stats::get_stats(&[], connection);
+            stats::display_content(
+                stats,
+                stats::PrintMode::ConcreteTasks,
+                Some(vec![task.task_id]),
+                Format::Table,
+                connection,
             );
         }
-        _ => (),
+        Err(x) => println!("{}", x),
+    }
+}
+
+pub fn edit_log(log_id: i32, date: NaiveDateTime, connection: &mut DbConnection) {
+    match db_operations::logs::edit_log(log_id, date, connection) {
+        Ok((old_log, new_log)) => println!(
+            "Moved log {} ({}) of task {} from {} to {}",
+            new_log.log_id,
+            new_log.log_type,
+            new_log.task_id,
+            old_log.date.format(&config::get().date_format),
+            new_log.date.format(&config::get().date_format)
+        ),
+        Err(x) => print_change_error(x),
+    }
+}
+
+pub fn delete_log(log_id: i32, connection: &mut DbConnection) {
+    match db_operations::logs::delete_log(log_id, connection) {
+        Ok(x) => println!(
+            "Deleted log {} ({}) of task {} from {}",
+            x.log_id,
+            x.log_type,
+            x.task_id,
+            x.date.format(&config::get().date_format)
+        ),
+        Err(x) => print_change_error(x),
     }
+}
 
-    let stats = db_operations::stats::get_stats(&[]);
-    let a = stats::display_content(stats, stats::PrintMode::ConcreteTasks, Some(vec![*num]));
-    db_operations::logs::add_log(*num, log_type.to_string(), false);
-    a
+pub fn undo(connection: &mut DbConnection) {
+    match db_operations::logs::undo_log(connection) {
+        Ok(x) => println!(
+            "Undone log {} ({}) of task {} from {}",
+            x.log_id,
+            x.log_type,
+            x.task_id,
+            x.date.format(&config::get().date_format)
+        ),
+        Err(x) => print_change_error(x),
+    }
+}
+
+fn print_change_error(error: LogError) {
+    match error {
+        LogError::NoSuchLog | LogError::NothingToUndo | LogError::Database(_) => {
+            println!("{}", error)
+        }
+        _ => println!("The change would break the order of logs: {}", error),
+    }
 }
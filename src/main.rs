@@ -1,5 +1,5 @@
-use std::env;
 use std::io;
+use std::process;
 
 use crossterm::execute;
 use crossterm::terminal::EnterAlternateScreen;
@@ -11,6 +11,12 @@ use crossterm::{
 use termion::event::Key;
 
 use termion::input::TermRead;
+use clap::{CommandFactory, Parser, ValueEnum};
+use clap_complete::Shell;
+use cli::{Cli, Commands, LogAction};
+use output::Format;
+use workflow::models::LogType;
+use workflow::DbConnection;
 use tui::{
     backend::CrosstermBackend,
     layout::{Constraint, Layout},
@@ -19,105 +25,171 @@ use tui::{
 };
 
 pub mod apps;
+pub mod cli;
 pub mod db_operations;
 pub mod logs;
+pub mod output;
 pub mod projects;
 pub mod stats;
+pub mod status;
 pub mod tasks;
 pub mod pomodoro;
+pub mod pomodoros;
 
-#[derive(Debug, PartialEq, Clone)]
-pub enum Commands {
-    AddProject,
-    AllProjects,
-    ProjectApps,
-    ProjectTasks,
-    AddTask,
-    AddApp,
-    AllApps,
-    AddAppToTask,
-    AllTasks,
-    Begin,
-    Pause,
-    Resume,
-    End,
-    Logs,
-    Man,
-    Stats,
-    Day,
-    Pomodoro,
-    NoSuchCommand,
-}
-
-impl From<String> for Commands {
-    fn from(input: String) -> Self {
-        match input.trim() {
-            "newproject" => Commands::AddProject,
-            "projects" => Commands::AllProjects,
-            "newapp" => Commands::AddApp,
-            "apps" => Commands::AllApps,
-            "newtask" => Commands::AddTask,
-            "tasks" => Commands::AllTasks,
-            "logs" => Commands::Logs,
-            "app-task" => Commands::AddAppToTask,
-            "stats" => Commands::Stats,
-            "begin" => Commands::Begin,
-            "end" => Commands::End,
-            "pause" => Commands::Pause,
-            "resume" => Commands::Resume,
-            "man" => Commands::Man,
-            "day" => Commands::Day,
-            "projectapps"=>Commands::ProjectApps,
-            "projecttasks"=>Commands::ProjectTasks,
-            "pomodoro"=>Commands::Pomodoro,
-            _ => Commands::NoSuchCommand,
+fn main() {
+    let config = match workflow::config::load() {
+        Ok(x) => x,
+        Err(x) => {
+            eprintln!("{}", x);
+            process::exit(-1);
         }
+    };
+    let cli = Cli::parse();
+
+    match cli.command {
+        Commands::Man { roff } => return display_man(roff),
+        Commands::Completions { shell } => return print_completions(shell),
+        _ => (),
     }
-}
 
-impl ToString for Commands {
-    fn to_string(&self) -> String {
-        match self {
-            Self::Begin => String::from("B"),
-            Self::End => String::from("E"),
-            Self::Pause => String::from("P"),
-            Self::Resume => String::from("R"),
-            _ => String::from(""),
+    let connection = &mut workflow::establish_connection();
+    migrate(matches!(cli.command, Commands::Migrate), connection);
+
+    let format = match (cli.format, &config.format) {
+        (Some(x), _) => x,
+        (None, Some(x)) => match Format::from_str(x, true) {
+            Ok(x) => x,
+            Err(_) => {
+                eprintln!("Unknown format {} in the config, expected table, json, csv or tsv", x);
+                process::exit(-1);
+            }
+        },
+        (None, None) => Format::Table,
+    };
+    match cli.command {
+        Commands::AddApp { apps } => apps::add_app(&apps, true, connection),
+        Commands::AddTask {
+            name,
+            project,
+            planned_time,
+        } => match project.as_ref().or(config.default_project.as_ref()) {
+            Some(project) => tasks::add_task(&name, project, planned_time, connection),
+            None => println!("No project given and no default_project in the config"),
+        },
+        Commands::AllTasks { all } => tasks::display_tasks(all, format, connection),
+        Commands::EditTask {
+            task,
+            name,
+            planned_time,
+        } => tasks::edit_task(&task, name.as_deref(), planned_time, connection),
+        Commands::MoveTask { task, project } => tasks::move_task(&task, &project, connection),
+        Commands::ArchiveTask { task, unarchive } => {
+            tasks::archive_task(&task, !unarchive, connection)
         }
-    }
+        Commands::DeleteTask { task, cascade } => tasks::delete_task(&task, cascade, connection),
+        Commands::Begin(x) => logs::add_log(&x.task, LogType::Begin, x.at, connection),
+        Commands::Pause(x) => logs::add_log(&x.task, LogType::Pause, x.at, connection),
+        Commands::Resume(x) => logs::add_log(&x.task, LogType::Resume, x.at, connection),
+        Commands::End(x) => logs::add_log(&x.task, LogType::End, x.at, connection),
+        Commands::Switch(x) => logs::switch(&x.task, x.at, connection),
+        Commands::Logs { tasks } => logs::display_logs(&tasks, format, connection),
+        Commands::Log {
+            action: LogAction::Edit { log_id, at },
+        } => logs::edit_log(log_id, at, connection),
+        Commands::Log {
+            action: LogAction::Delete { log_id },
+        } => logs::delete_log(log_id, connection),
+        Commands::Undo => logs::undo(connection),
+        Commands::Stats => stats::display_stats(format, connection),
+        Commands::Status { short } => status::display_status(short, format, connection),
+        Commands::AddProject {
+            name,
+            planned_time,
+            apps,
+        } => projects::add_project(&name, planned_time, &apps, connection),
+        Commands::AllProjects { all } => projects::display_projects(all, format, connection),
+        Commands::EditProject {
+            project,
+            name,
+            planned_time,
+        } => projects::edit_project(&project, name.as_deref(), planned_time, connection),
+        Commands::ArchiveProject { project, unarchive } => {
+            projects::archive_project(&project, !unarchive, connection)
+        }
+        Commands::DeleteProject { project, cascade } => {
+            projects::delete_project(&project, cascade, connection)
+        }
+        Commands::Day { long, date, live } => {
+            stats::display_day_stats(date, long, live, format, connection)
+        }
+        Commands::Report(x) => match x.period.range(workflow::config::now().date()) {
+            Ok((from, to)) => stats::display_report(from, to, x.live, format, connection),
+            Err(x) => println!("{}", x),
+        },
+        Commands::Pomodoros(x) => match x.range(workflow::config::now().date()) {
+            Ok((from, to)) => pomodoros::display_pomodoros(from, to, format, connection),
+            Err(x) => println!("{}", x),
+        },
+        Commands::AllApps => apps::display_apps(format, connection),
+        Commands::AddAppToProject {
+            project,
+            apps,
+            remove,
+        } => apps::manage_project_apps(&project, &apps, remove, connection),
+        Commands::AddAppToTask { task, apps, remove } => {
+            apps::manage_task_apps(&task, &apps, remove, connection)
+        }
+        Commands::ProjectApps => projects::display_project_apps(format, connection),
+        Commands::ProjectTasks {
+            projects,
+            begun,
+            not_begun,
+            ended,
+            not_ended,
+        } => projects::display_project_tasks(
+            projects,
+            cli::filter(begun, not_begun),
+            cli::filter(ended, not_ended),
+            format,
+            connection,
+        ),
+        Commands::Pomodoro { task: None, .. } => pomodoro::resume(connection),
+        Commands::Pomodoro {
+            task: Some(task),
+            cycle,
+            ..
+        } => pomodoro::pomodoro(
+            &task,
+            cycle.map(|x| x.unwrap_or_else(pomodoro::Cycle::from_config)),
+            connection,
+        ),
+        Commands::Migrate | Commands::Man { .. } | Commands::Completions { .. } => (),
+    };
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-
-    if args.len() < 2 {
-        println!("Issue a command!");
-        return;
-    }
+fn print_completions(shell: Shell) {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, &mut io::stdout());
+}
 
-    let command = Commands::from(args[1].clone());
-    match command {
-        Commands::AddApp => apps::add_app(&args[2..], true),
-        Commands::AddTask => tasks::add_task(args),
-        Commands::AllTasks => tasks::display_tasks(),
-        Commands::Begin | Commands::End | Commands::Pause | Commands::Resume => {
-            logs::add_log(args, command)
+fn migrate(display_communicates: bool, connection: &mut DbConnection) {
+    match workflow::run_migrations(connection) {
+        Ok(versions) => {
+            if display_communicates {
+                if versions.is_empty() {
+                    println!("Database is up to date");
+                }
+                for version in versions {
+                    println!("Applied migration {}", version);
+                }
+            }
         }
-        Commands::Logs => logs::display_logs(&args[2..]),
-        Commands::Man => display_man(),
-        Commands::Stats => stats::display_stats(&args[2..]),
-        Commands::AddProject => projects::add_project(args),
-        Commands::AllProjects => projects::display_projects(),
-        Commands::Day => stats::display_day_stats(&args[2..]),
-        Commands::AllApps=> apps::display_apps(&args[2..]),
-        Commands::ProjectApps=> projects::display_project_apps(),
-        Commands::ProjectTasks=>projects::display_project_tasks(&args[2..]),
-        Commands::Pomodoro=>pomodoro::pomodoro(&args[2..]),
-        Commands::NoSuchCommand => {
-            println!("Wrong command!");
+        Err(x) => {
+            eprintln!("Error running migrations: {}", x);
+            process::exit(-1);
         }
-        _ => (),
-    };
+    }
 }
 
 struct App {
@@ -128,102 +200,43 @@ struct App {
 impl App {
     fn handle_input(&mut self, key: Key) {
         match key {
-            Key::Up => {
-                if self.scroll_position > 0 {
-                    self.scroll_position -= 1;
-                }
+            Key::Up if self.scroll_position > 0 => {
+                self.scroll_position -= 1;
             }
-            Key::Down => {
-                // You can adjust the scroll limit according to your content
-                if self.scroll_position < self.msg_end {
-                    self.scroll_position += 1;
-                }
+            // You can adjust the scroll limit according to your content
+            Key::Down if self.scroll_position < self.msg_end => {
+                self.scroll_position += 1;
             }
             _ => {}
         }
     }
 }
 
-fn display_man() {
-    let message=
-"-----------------------------------------------------
-                Welcome to workflow!
------------------------------------------------------
-COMMANDS:
-- newproject NAME- creates new project
-    OPTIONS:
-        -t TIME - sets time user plans to spend on project. It should have format DAYS:HOURS:MINUTES
-        -a APPLIST - sets apps used in the project;
-
-- newtask NAME PROJECT_ID - creates new task in a project of the given id
-    OPTIONS:
-        -t TIME - sets time user plans to spend on task. It should have format DAYS:HOURS:MINUTES;
-
-- tasks - displays all tasks;
-
-- app APPLIST - adds specified apps to db;
-
-- begin ID/NAME - begins the task given by id or name; a task that has ended cannot be started again;
-
-- end ID/NAME - ends the task given by id or name; cannot end a task that was not started;
-
-- pause ID/NAME - pauses the task given by id or name, a task that has already been paused, has ended 
-                or has not begun cannot be paused;
-
-- resume ID/NAME - resumes the task given by id or name, a task that has not been paused, has ended 
-                    or has not begun cannot be resumed;     
-
-- logs - displays the history of all tasks
-    OPTIONS
-        -t, -tasks TASKIDLIST - displays only the history of the tasks specified in args by id;
-
-- stats - displays stats for every task and project, i.e. number of pauses made during the task,
-            time spent working etc.;
-
-- day - displays day stats: a table of projects cdeveloped on the current day
-    OPTIONS
-        -l - displays additional information for every project developed on the current day and 
-            tasks within it
-        -d, -date DATE - sets the date to display instead of the current date, DATE should be 
-            in format \"YYYY-MM-DD\"
-
-        (possible merging -ld, -dl options);
-
-- projectapps - displays all apps the projects use;
-
-- projecttasks - displays all tasks belonging to the projects
-    OPTIONS
-        -pr PROJECT_ID_LIST - displays the tasks only for the given projects
-        -b - displays only the tasks that have been beginned
-        -nb - displays only the tasks that have not been beginned
-        -e - displays only the tasks that have been ended
-        -ne - displays only the tasks that have not been ended;
-    
-    Returns error if two contraditory filters are applied, i.e. -b and -nb;
-
-- pomodoro PROJECT_ID - enables pomodoro mode for a certain project. The project must be started but not ended.
-     When executed, a console with a clock is opened in terminal and one can issue following commands:
-        pause HH:MM:SS - the timer counts down the given time, so as to stop counting, press CTRL + \\.
-                         At the end one is asked if a log \"resume\" (back to work) should be added. 
-                        Responses are \"yes\" or \"no\"
-
-        work HH:MM:SS - the timer counts down the given time, so as to stop counting, press CTRL + \\.
-                         At the end one is asked if a log \"pause\" (pause work) should be added. 
-                        Responses are \"yes\" or \"no\"
-
-        currenttask - displays stats for the currenttask (with task_id)
-
-        changetask TASK_ID - changes task for the one with the given id when it exists, is 
-                            started and hasn't ended yet
-
-        clear - clears terminal
-
-        exit - exits the pomodoro mode
-
-    OPTIONS
-        clearing - enables clearing terminal when its overflown;
+fn display_man(roff: bool) {
+    let mut command = Cli::command();
+    if roff {
+        if let Err(x) = clap_mangen::Man::new(command).render(&mut io::stdout()) {
+            eprintln!("Error rendering man page: {}", x);
+        }
+        return;
+    }
 
-- man - displays app's manual;";
+    command.build();
+    let mut message = format!(
+        "-----------------------------------------------------\n\
+        \x20               Welcome to workflow!\n\
+        -----------------------------------------------------\n\
+        {}\nCOMMANDS:\n",
+        command.render_usage()
+    );
+    for subcommand in command.get_subcommands_mut().filter(|x| x.get_name() != "help") {
+        let help = subcommand.render_long_help().to_string();
+        message.push_str(&format!("\n- {}\n", subcommand.get_name()));
+        for line in help.lines() {
+            message.push_str(&format!("    {}\n", line));
+        }
+    }
+    let message = message.as_str();
 
     enable_raw_mode().expect("Failed to enable raw mode");
     let mut stdout = io::stdout();
@@ -255,31 +268,29 @@ COMMANDS:
         })
         .expect("Error displaying man");
 
-    for key in io::stdin().keys() {
-        if let Ok(key) = key {
-            match key {
-                Key::Char('q') => break,
-                _ => {
-                    app.handle_input(key);
-                    terminal
-                        .draw(|f| {
-                            let chunks = Layout::default()
-                                .constraints([Constraint::Percentage(100)].as_ref())
-                                .margin(2)
-                                .split(f.size());
-
-                            let scrollable_text = message
-                                .lines()
-                                .skip(app.scroll_position as usize)
-                                .collect::<Vec<_>>()
-                                .join("\n");
-
-                            let paragraph = Paragraph::new(scrollable_text)
-                                .block(Block::default().title("Man").borders(Borders::ALL));
-                            f.render_widget(paragraph, chunks[0]);
-                        })
-                        .unwrap();
-                }
+    for key in io::stdin().keys().map_while(Result::ok) {
+        match key {
+            Key::Char('q') => break,
+            _ => {
+                app.handle_input(key);
+                terminal
+                    .draw(|f| {
+                        let chunks = Layout::default()
+                            .constraints([Constraint::Percentage(100)].as_ref())
+                            .margin(2)
+                            .split(f.size());
+
+                        let scrollable_text = message
+                            .lines()
+                            .skip(app.scroll_position as usize)
+                            .collect::<Vec<_>>()
+                            .join("\n");
+
+                        let paragraph = Paragraph::new(scrollable_text)
+                            .block(Block::default().title("Man").borders(Borders::ALL));
+                        f.render_widget(paragraph, chunks[0]);
+                    })
+                    .unwrap();
             }
         }
     }
@@ -0,0 +1,60 @@
+use clap::ValueEnum;
+use serde::{Serialize, Serializer};
+use std::io;
+
+/// Format of listings and stats, set with the global `--format` option.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    /// A table for reading in terminal
+    #[default]
+    Table,
+    /// A JSON array of objects
+    Json,
+    /// Comma-separated values with a header row
+    Csv,
+    /// Tab-separated values with a header row
+    Tsv,
+}
+
+/// Prints the rows in one of the machine-readable formats; tables are built by the callers
+/// themselves, so nothing is printed for `Format::Table`.
+pub fn print_rows<T: Serialize>(rows: &[T], format: Format) {
+    let result = match format {
+        Format::Table => return,
+        Format::Json => serde_json::to_writer_pretty(io::stdout(), rows)
+            .map(|_| println!())
+            .map_err(|x| x.to_string()),
+        Format::Csv | Format::Tsv => {
+            let mut writer = csv::WriterBuilder::new()
+                .delimiter(if format == Format::Tsv { b'\t' } else { b',' })
+                .from_writer(io::stdout());
+            rows.iter()
+                .try_for_each(|row| writer.serialize(row))
+                .and_then(|_| writer.flush().map_err(csv::Error::from))
+                .map_err(|x| x.to_string())
+        }
+    };
+
+    if let Err(x) = result {
+        eprintln!("Error printing output: {}", x);
+    }
+}
+
+/// Serializes a duration as a whole number of seconds.
+pub fn as_seconds<S: Serializer>(
+    duration: &chrono::Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_i64(duration.num_seconds())
+}
+
+/// Serializes an optional duration as a number of seconds, or as null when there is none.
+pub fn as_optional_seconds<S: Serializer>(
+    duration: &Option<chrono::Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match duration {
+        Some(x) => serializer.serialize_some(&x.num_seconds()),
+        None => serializer.serialize_none(),
+    }
+}
@@ -1,566 +1,1046 @@
-use chrono::NaiveTime;
-use crossterm::{cursor, terminal, ExecutableCommand};
-use regex::Regex;
-use std::io::{self, stdout, Write};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use std::time::Duration;
-use std::{process, thread};
-use terminal_fonts::{map_block, to_block, to_string};
-use termion::terminal_size;
-
-use crate::{db_operations, logs, stats, Commands};
-
-fn green(v: &str) -> String {
-    format!("{}{}{}", "\u{001b}[32m", v, "\u{001b}[0m")
-}
+use std::io::{self, Write};
+use std::process::{self, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
 
-fn red(v: &str) -> String {
-    format!("{}{}{}", "\u{001b}[31m", v, "\u{001b}[0m")
-}
-enum PomodoroCommands {
-    Yes,
-    No,
-    Work,
-    Pause,
-    End,
-    Exit,
-    ChangeTask,
-    CurrentTask,
-    Clear,
-    NoSuchCommand,
+use chrono::{NaiveDateTime, NaiveTime};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use terminal_fonts::to_block_string;
+use tui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Span, Spans, Text},
+    widgets::{Block, Borders, Paragraph},
+    Frame, Terminal,
+};
+use workflow::config;
+use workflow::models::{LogType, NewPomodoroSession, PomodoroKind, PomodoroTimer, Task};
+use workflow::DbConnection;
+
+use crate::{db_operations, logs, stats, tasks};
+
+/// How often the clock is redrawn when no key is pressed.
+const TICK: Duration = Duration::from_millis(200);
+
+/// How often the stats of the task are fetched again when nothing changes them.
+const STATS_REFRESH: Duration = Duration::from_secs(60);
+
+/// How often the timer is saved when nothing changes it, to show the mode is still running.
+const HEARTBEAT: Duration = Duration::from_secs(15);
+
+/// A timer not saved for that long was left by a terminal that was closed or crashed.
+const STALE_AFTER: Duration = Duration::from_secs(60);
+
+/// Lengths of a pomodoro cycle: rounds of work separated by short breaks, with a long break
+/// after every `rounds` rounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub work: chrono::Duration,
+    pub short_break: chrono::Duration,
+    pub long_break: chrono::Duration,
+    pub rounds: u32,
 }
 
-impl From<String> for PomodoroCommands {
-    fn from(input: String) -> Self {
-        match input.trim() {
-            "yes" => PomodoroCommands::Yes,
-            "no" => PomodoroCommands::No,
-            "work" => PomodoroCommands::Work,
-            "pause" => PomodoroCommands::Pause,
-            "end" => PomodoroCommands::End,
-            "exit" => PomodoroCommands::Exit,
-            "changetask" => PomodoroCommands::ChangeTask,
-            "currenttask" => PomodoroCommands::CurrentTask,
-            "clear" => PomodoroCommands::Clear,
-            _ => PomodoroCommands::NoSuchCommand,
-        }
+impl Cycle {
+    /// Parses the minutes of work, of the short break and of the long break followed by the
+    /// number of rounds, e.g. `25/5/15x4`.
+    pub fn parse(input: &str) -> Option<Cycle> {
+        let (lengths, rounds) = input.trim().split_once('x')?;
+        let lengths = lengths
+            .split('/')
+            .map(|x| {
+                x.parse::<i64>()
+                    .ok()
+                    .filter(|x| (1..24 * 60).contains(x))
+                    .map(chrono::Duration::minutes)
+            })
+            .collect::<Option<Vec<chrono::Duration>>>()?;
+        let [work, short_break, long_break] = lengths[..] else {
+            return None;
+        };
+
+        Some(Cycle {
+            work,
+            short_break,
+            long_break,
+            rounds: rounds.parse().ok().filter(|x| *x > 0)?,
+        })
     }
-}
-pub fn pomodoro(args: &[String]) {
-    if args.len() < 1 {
-        eprintln!("Too few args");
-        process::exit(-1);
+
+    /// The cycle the timer was saved with, if any.
+    fn from_timer(timer: &PomodoroTimer) -> Option<Cycle> {
+        Some(Cycle {
+            work: chrono::Duration::seconds(timer.work_seconds?.into()),
+            short_break: chrono::Duration::seconds(timer.break_seconds?.into()),
+            long_break: chrono::Duration::seconds(timer.long_break_seconds?.into()),
+            rounds: u32::try_from(timer.rounds?).ok().filter(|x| *x > 0)?,
+        })
     }
 
-    if args.len() > 2 {
-        eprintln!("Too many args");
-        process::exit(-1);
+    /// The cycle of the pomodoro lengths in the config.
+    pub fn from_config() -> Cycle {
+        let config = config::get();
+        Cycle {
+            work: config.pomodoro_work - NaiveTime::MIN,
+            short_break: config.pomodoro_pause - NaiveTime::MIN,
+            long_break: config.pomodoro_long_break - NaiveTime::MIN,
+            rounds: config.pomodoro_rounds,
+        }
     }
-    let mut commands_num = 2;
+}
 
-    let task_id = &args[0].parse::<i32>();
-    let clearing = args.len() > 1 && args[1] == "clearing";
+/// The work or break being counted down, saved as a pomodoro session when it ends.
+struct Phase {
+    kind: PomodoroKind,
+    /// Shown above the clock, e.g. "Round 2 (2/4): work".
+    title: String,
+    started_at: NaiveDateTime,
+    planned: chrono::Duration,
+    /// Time left when the clock was last started or stopped.
+    left: Duration,
+    /// When the clock was last started, missing while it's stopped.
+    running_since: Option<Instant>,
+}
 
-    let task_id = match task_id {
-        Err(_) => {
-            println!("Wrong argument!");
-            return;
+impl Phase {
+    fn new(kind: PomodoroKind, title: String, planned: chrono::Duration) -> Phase {
+        Phase {
+            kind,
+            title,
+            started_at: config::now(),
+            planned,
+            left: planned.to_std().unwrap_or_default(),
+            running_since: Some(Instant::now()),
         }
-        Ok(x) => match db_operations::tasks::find_task_by_id(x) {
-            Ok(Some(_)) => x,
-            Ok(None) => {
-                println!("No such task!");
-                return;
-            }
-            Err(x) => {
-                println!("{}", x);
-                return;
-            }
-        },
-    };
+    }
 
-    if !pomodoro_possible(Commands::Pause, task_id) {
-        process::exit(-1);
+    /// The phase of a saved timer, its clock running again if it was running.
+    fn from_timer(timer: &PomodoroTimer) -> Phase {
+        let left = match timer.deadline {
+            Some(x) => (x - config::now()).to_std().unwrap_or_default(),
+            None => Duration::from_secs(timer.remaining_seconds.max(0) as u64),
+        };
+        Phase {
+            kind: timer.kind,
+            title: timer.title.clone(),
+            started_at: timer.started_at,
+            planned: chrono::Duration::seconds(timer.planned_seconds.into()),
+            left,
+            running_since: timer.deadline.map(|_| Instant::now()),
+        }
     }
 
-    let mut stdout = stdout();
+    fn remaining(&self) -> Duration {
+        match self.running_since {
+            Some(x) => self.left.saturating_sub(x.elapsed()),
+            None => self.left,
+        }
+    }
 
-    ctrlc::set_handler(move || {
-        let mut stdout = io::stdout();
+    /// Stops the clock if it's running, or starts it again.
+    fn toggle(&mut self) {
+        match self.running_since.take() {
+            Some(x) => self.left = self.left.saturating_sub(x.elapsed()),
+            None => self.running_since = Some(Instant::now()),
+        }
+    }
 
-        stdout.execute(cursor::Show).unwrap();
-        stdout.execute(cursor::MoveToColumn(0)).unwrap();
-        stdout
-            .execute(cursor::MoveDown(commands_num as u16))
-            .unwrap();
-        stdout
-            .execute(terminal::Clear(terminal::ClearType::FromCursorDown))
-            .unwrap();
+    /// The session ending at `ended_at`, `completed` if the clock ran down to zero.
+    fn session(
+        &self,
+        task_id: i32,
+        completed: bool,
+        ended_at: NaiveDateTime,
+    ) -> NewPomodoroSession {
+        let actual = ended_at - self.started_at;
+        NewPomodoroSession {
+            task_id,
+            kind: self.kind,
+            started_at: self.started_at,
+            planned_seconds: self.planned.num_seconds() as i32,
+            actual_seconds: actual.num_seconds().max(0) as i32,
+            completed,
+        }
+    }
+}
 
-        println!("Exiting...");
-        io::stdout().flush().unwrap();
-        process::exit(0);
-    })
-    .expect("Error setting Ctrl-C handler");
+/// Events of the pomodoro mode with commands run on them, see `config::Hooks`.
+#[derive(Debug, Clone, Copy)]
+enum Hook {
+    WorkStarted,
+    WorkFinished,
+    BreakFinished,
+    SessionEnded,
+}
 
-    let term = Arc::new(AtomicBool::new(false));
+impl Hook {
+    fn name(self) -> &'static str {
+        match self {
+            Hook::WorkStarted => "work_started",
+            Hook::WorkFinished => "work_finished",
+            Hook::BreakFinished => "break_finished",
+            Hook::SessionEnded => "session_ended",
+        }
+    }
 
-    signal_hook::flag::register(signal_hook::consts::SIGQUIT, Arc::clone(&term)).unwrap();
-    stdout
-        .execute(terminal::Clear(terminal::ClearType::All))
-        .unwrap();
-    stdout.execute(cursor::Hide).unwrap();
-    let zero_secs = NaiveTime::parse_from_str("00:00:00", "%H:%M:%S").unwrap();
+    fn command(self) -> Option<&'static str> {
+        let hooks = &config::get().hooks;
+        match self {
+            Hook::WorkStarted => hooks.work_started.as_deref(),
+            Hook::WorkFinished => hooks.work_finished.as_deref(),
+            Hook::BreakFinished => hooks.break_finished.as_deref(),
+            Hook::SessionEnded => hooks.session_ended.as_deref(),
+        }
+    }
+}
 
-    let mut count_time = NaiveTime::parse_from_str("00:00:00", "%H:%M:%S").unwrap();
+/// State of the pomodoro mode.
+struct Pomodoro {
+    task: Task,
+    cycle: Option<Cycle>,
+    /// Number of the current round of the cycle, counted from 1.
+    round: u32,
+    phase: Option<Phase>,
+    /// Log asked about after the clock of the manual mode ends.
+    question: Option<LogType>,
+    /// Command being typed after ':'.
+    input: Option<String>,
+    messages: Vec<String>,
+    /// Number of lines the message pane is scrolled up from its end.
+    scroll: usize,
+    stats: Vec<(&'static str, String)>,
+    stats_at: Instant,
+    /// The terminal running the mode, as `host:pid`, saved with the timer.
+    owner: String,
+    /// The timer has to be saved on the next tick.
+    timer_changed: bool,
+    timer_saved_at: Instant,
+    quit: bool,
+}
+
+pub fn pomodoro(task: &str, cycle: Option<Cycle>, connection: &mut DbConnection) {
+    let Some(task) = tasks::resolve_task(task, connection) else {
+        return;
+    };
+    if let Err(x) = pomodoro_possible(task.task_id, connection) {
+        println!("{}", x);
+        process::exit(-1);
+    }
+    if !check_timer(connection) {
+        process::exit(-1);
+    }
 
-    let time_str: String = count_time.format("%H:%M:%S").to_string();
-    stdout.execute(cursor::MoveTo(0, 0)).unwrap();
-    let time_str: String = to_string(&map_block(&to_block(&time_str), red));
+    let mut app = Pomodoro::new(task, cycle, connection);
+    match cycle {
+        Some(_) => app.start_cycle_phase(PomodoroKind::Work, connection),
+        None => app.message("Type :work or :pause to start the clock"),
+    }
+    show(app, connection);
+}
 
-    println!("{}", red(&time_str));
-    println!("\n");
-    loop {
-        stdout.execute(cursor::Show).unwrap();
-
-        let last_command = action_commands(
-            &mut commands_num,
-            &mut count_time,
-            time_str.lines().count() as i32,
-            *task_id,
-            &clearing,
+/// Continues the pomodoro mode left by a terminal that was closed or crashed, with its clock,
+/// round and cycle.
+pub fn resume(connection: &mut DbConnection) {
+    let timer = match db_operations::pomodoros::get_timer(&config::get().username, connection) {
+        Ok(Some(x)) => x,
+        Ok(None) => return println!("There is no pomodoro to resume"),
+        Err(x) => return println!("{}", x),
+    };
+    if !is_stale(&timer) {
+        println!(
+            "The pomodoro of task {} is running in another terminal",
+            timer.task_id
         );
-        stdout.execute(cursor::Hide).unwrap();
-
-        while count_time != zero_secs {
-            show_clock(&mut count_time, &term);
-            if count_time != zero_secs {
-                clock_stopped(
-                    &mut commands_num,
-                    &mut count_time,
-                    &term,
-                    time_str.lines().count() as i32,
-                    &clearing,
-                );
-                (term).store(false, Ordering::Relaxed);
-            }
+        process::exit(-1);
+    }
+    let task = match db_operations::tasks::find_task_by_id(&timer.task_id, connection) {
+        Ok(Some(x)) => x,
+        Ok(None) => return println!("No such task!"),
+        Err(x) => return println!("{}", x),
+    };
+    if let Err(x) = pomodoro_possible(task.task_id, connection) {
+        println!("{}", x);
+        discard_timer(&timer, connection);
+        process::exit(-1);
+    }
+    if let Err(x) =
+        db_operations::pomodoros::delete_timer(&timer.username, &timer.owner, connection)
+    {
+        println!("{}", x);
+        process::exit(-1);
+    }
+
+    let mut app = Pomodoro::new(task, Cycle::from_timer(&timer), connection);
+    app.round = u32::try_from(timer.round).unwrap_or(1).max(1);
+    let phase = Phase::from_timer(&timer);
+    match timer.deadline {
+        Some(x) if x <= config::now() => app.finish_expired(phase, x, connection),
+        _ => {
+            app.phase = Some(phase);
+            app.message(format!("Resumed {}", timer.title));
         }
+    }
+    show(app, connection);
+}
 
-        let time_str: String = count_time.format("%H:%M:%S").to_string();
-        stdout.execute(cursor::MoveTo(0, 0)).unwrap();
-        let time_str: String = to_string(&map_block(&to_block(&time_str), red));
-
-        println!("{}", red(&time_str));
-
-        stdout.execute(cursor::Show).unwrap();
-        stdout.execute(cursor::MoveToColumn(0)).unwrap();
-        stdout
-            .execute(cursor::MoveDown(commands_num as u16))
-            .unwrap();
-        save_log(
-            &mut commands_num,
-            &mut count_time,
-            last_command,
-            &task_id,
-            time_str.lines().count() as i32,
-            &clearing,
-        );
+/// Opens the screen of the pomodoro mode until it's quit.
+fn show(mut app: Pomodoro, connection: &mut DbConnection) {
+    enable_raw_mode().expect("Failed to enable raw mode");
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen).unwrap();
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend).expect("Failed to initialize terminal");
 
-        io::stdout().flush().unwrap();
+    let result = run(&mut terminal, &mut app, connection);
 
-        stdout.execute(cursor::Hide).unwrap();
+    disable_raw_mode().expect("Error ending raw mode");
+    execute!(terminal.backend_mut(), LeaveAlternateScreen).expect("");
+    terminal.show_cursor().expect("");
+    if let Err(x) = result {
+        println!("{}", x);
     }
 }
 
-fn show_clock(count_time: &mut NaiveTime, term: &Arc<AtomicBool>) {
-    let mut stdout = stdout();
-    stdout.execute(cursor::Hide).unwrap();
+/// Redraws the screen on every tick or key press until the mode is quit.
+fn run<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut Pomodoro,
+    connection: &mut DbConnection,
+) -> io::Result<()> {
+    while !app.quit {
+        terminal.draw(|f| app.draw(f))?;
+        if event::poll(TICK)? {
+            if let Event::Key(key) = event::read()? {
+                app.handle_key(key, connection);
+            }
+        }
+        app.tick(connection);
+    }
+    Ok(())
+}
 
-    let zero_secs = NaiveTime::parse_from_str("00:00:00", "%H:%M:%S").unwrap();
-    let one_sec = Duration::from_secs(1);
-    while *count_time > zero_secs && !(*term).load(Ordering::Relaxed) {
-        let time_str: String = count_time.format("%H:%M:%S").to_string();
+impl Pomodoro {
+    fn new(task: Task, cycle: Option<Cycle>, connection: &mut DbConnection) -> Pomodoro {
+        let mut app = Pomodoro {
+            task,
+            cycle,
+            round: 1,
+            phase: None,
+            question: None,
+            input: None,
+            messages: vec![],
+            scroll: 0,
+            stats: vec![],
+            stats_at: Instant::now(),
+            owner: format!("{}:{}", whoami::devicename(), process::id()),
+            timer_changed: true,
+            timer_saved_at: Instant::now(),
+            quit: false,
+        };
+        app.refresh_stats(connection);
+        app
+    }
 
-        stdout.execute(cursor::MoveTo(0, 0)).unwrap();
-        let time_str: String = to_string(&map_block(&to_block(&time_str), green));
+    fn message(&mut self, message: impl AsRef<str>) {
+        self.messages
+            .extend(message.as_ref().lines().map(str::to_string));
+        self.scroll = 0;
+    }
 
-        println!("{}", green(&time_str));
+    fn refresh_stats(&mut self, connection: &mut DbConnection) {
+        match stats::task_summary(self.task.task_id, connection) {
+            Ok(x) => self.stats = x,
+            Err(x) => self.message(x),
+        }
+        self.stats_at = Instant::now();
+    }
 
-        stdout.flush().unwrap();
+    fn handle_key(&mut self, key: KeyEvent, connection: &mut DbConnection) {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return self.exit(connection);
+        }
 
-        *count_time = *count_time - chrono::Duration::seconds(1);
+        if let Some(input) = self.input.as_mut() {
+            match key.code {
+                KeyCode::Char(x) => input.push(x),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Esc => self.input = None,
+                KeyCode::Enter => {
+                    let command = self.input.take().unwrap_or_default();
+                    self.command(&command, connection);
+                }
+                _ => (),
+            }
+            return;
+        }
 
-        thread::sleep(one_sec);
+        match key.code {
+            KeyCode::Char('y') if self.question.is_some() => {
+                if let Some(log_type) = self.question.take() {
+                    self.add_log(log_type, config::now(), connection);
+                    self.refresh_stats(connection);
+                }
+            }
+            KeyCode::Char('n') if self.question.is_some() => self.question = None,
+            KeyCode::Char('q') => self.exit(connection),
+            KeyCode::Char(' ') => match self.phase.as_mut() {
+                Some(phase) => {
+                    phase.toggle();
+                    self.timer_changed = true;
+                    let message = if phase.running_since.is_some() {
+                        "Clock running"
+                    } else {
+                        "Clock stopped"
+                    };
+                    self.message(message);
+                }
+                None => self.message("The clock isn't counting down"),
+            },
+            KeyCode::Char('s') => match self.phase {
+                Some(_) => self.finish_phase(false, connection),
+                None => self.message("The clock isn't counting down"),
+            },
+            KeyCode::Char(':') => self.input = Some(String::new()),
+            KeyCode::Up | KeyCode::PageUp => self.scroll += 1,
+            KeyCode::Down | KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(1),
+            _ => (),
+        }
     }
-    if *count_time == zero_secs {
-        print!("\x07");
+
+    /// Ends the phase whose clock ran down and saves the timer.
+    fn tick(&mut self, connection: &mut DbConnection) {
+        if self.phase.as_ref().is_some_and(|x| x.remaining().is_zero()) {
+            print!("\x07");
+            self.finish_phase(true, connection);
+        } else if self.stats_at.elapsed() >= STATS_REFRESH {
+            self.refresh_stats(connection);
+        }
+        if self.timer_changed || self.timer_saved_at.elapsed() >= HEARTBEAT {
+            self.save_timer(connection);
+        }
     }
-}
-fn clock_stopped(
-    commands_num: &mut i32,
-    count_time: &mut NaiveTime,
-    term: &Arc<AtomicBool>,
-    line_count: i32,
-    clearing: &bool,
-) {
-    let mut stdout = stdout();
-    let time_str: String = count_time.format("%H:%M:%S").to_string();
-    stdout.execute(cursor::MoveTo(0, 0)).unwrap();
-    let time_str: String = to_string(&map_block(&to_block(&time_str), red));
-
-    println!("{}", red(&time_str));
-
-    stdout.execute(cursor::Show).unwrap();
-    stdout.execute(cursor::MoveToColumn(0)).unwrap();
-    stdout
-        .execute(cursor::MoveDown(*commands_num as u16))
-        .unwrap();
-    loop {
-        print!("Clock was stopped, continue counting? [yes/no]: ");
-        io::stdout().flush().unwrap();
-        let mut command = String::new();
-        io::stdin()
-            .read_line(&mut command)
-            .expect("Failed to read line");
-        *commands_num += 1;
-
-        match PomodoroCommands::from(command) {
-            PomodoroCommands::Yes => {
-                println!("Clock running: to stop it press ctrl + \\");
-                *commands_num += 1;
-                show_clock(count_time, term);
-                return;
-            }
-            PomodoroCommands::No => {
-                *count_time = NaiveTime::parse_from_str("00:00:00", "%H:%M:%S").unwrap();
-                return;
+
+    /// Saves the timer of the phase counted down at the moment, or deletes it when there is
+    /// none.
+    fn save_timer(&mut self, connection: &mut DbConnection) {
+        self.timer_changed = false;
+        self.timer_saved_at = Instant::now();
+        let Some(phase) = &self.phase else {
+            let username = &config::get().username;
+            if let Err(x) =
+                db_operations::pomodoros::delete_timer(username, &self.owner, connection)
+            {
+                self.message(x);
             }
-            _ => {
-                println!("Wrong command, write yes or no!");
-                io::stdout().flush().unwrap();
-                *commands_num += 1;
+            return;
+        };
+
+        let now = config::now();
+        let remaining = phase.remaining();
+        let seconds = |x: chrono::Duration| x.num_seconds() as i32;
+        let timer = PomodoroTimer {
+            username: config::get().username.clone(),
+            owner: self.owner.clone(),
+            task_id: self.task.task_id,
+            kind: phase.kind,
+            title: phase.title.clone(),
+            started_at: phase.started_at,
+            planned_seconds: seconds(phase.planned),
+            deadline: phase
+                .running_since
+                .map(|_| now + chrono::Duration::from_std(remaining).unwrap_or_default()),
+            remaining_seconds: remaining.as_secs() as i32,
+            round: self.round as i32,
+            work_seconds: self.cycle.map(|x| seconds(x.work)),
+            break_seconds: self.cycle.map(|x| seconds(x.short_break)),
+            long_break_seconds: self.cycle.map(|x| seconds(x.long_break)),
+            rounds: self.cycle.map(|x| x.rounds as i32),
+            updated_at: now,
+        };
+        match db_operations::pomodoros::save_timer(&timer, connection) {
+            Ok(true) => (),
+            Ok(false) => {
+                self.phase = None;
+                self.message("The pomodoro was resumed in another terminal, quit with q");
             }
+            Err(x) => self.message(x),
         }
-        let size = terminal_size().unwrap().1 as i32;
-        if *clearing && commands_num > &mut (size - line_count) {
-            clear_terminal(commands_num, line_count);
+    }
+
+    /// Runs a command typed after ':'.
+    fn command(&mut self, command: &str, connection: &mut DbConnection) {
+        let args: Vec<&str> = command.split_whitespace().collect();
+        match args[..] {
+            [] => (),
+            ["work" | "pause", ..] if self.cycle.is_some() => {
+                self.message("The cycle starts the work and breaks itself, skip to them with s")
+            }
+            ["work", ..] => self.start_manual_phase(PomodoroKind::Work, args.get(1), connection),
+            ["pause", ..] => self.start_manual_phase(PomodoroKind::Break, args.get(1), connection),
+            ["task", ref task @ ..] if !task.is_empty() => {
+                self.change_task(&task.join(" "), connection)
+            }
+            ["task"] => self.message("No task given!"),
+            ["clear"] => {
+                self.messages.clear();
+                self.scroll = 0;
+            }
+            ["exit"] => self.exit(connection),
+            _ => self.message("Wrong command!"),
         }
+    }
 
-        let time_str: String = count_time.format("%H:%M:%S").to_string();
-        stdout.execute(cursor::MoveTo(0, 0)).unwrap();
-        let time_str: String = to_string(&map_block(&to_block(&time_str), red));
+    /// Saves the phase counted down at the moment as interrupted and quits.
+    fn exit(&mut self, connection: &mut DbConnection) {
+        if let Some(phase) = self.phase.take() {
+            self.save(&phase, false, config::now(), connection);
+        }
+        self.save_timer(connection);
+        self.run_hook(Hook::SessionEnded, None, None);
+        self.quit = true;
+    }
 
-        println!("{}", red(&time_str));
-        stdout
-            .execute(cursor::MoveDown(*commands_num as u16))
-            .unwrap();
+    /// Saves the phase ended at `ended_at` as a pomodoro session and runs the hook of its end.
+    fn save(
+        &mut self,
+        phase: &Phase,
+        completed: bool,
+        ended_at: NaiveDateTime,
+        connection: &mut DbConnection,
+    ) {
+        let session = phase.session(self.task.task_id, completed, ended_at);
+        if let Err(x) = db_operations::pomodoros::add_session(&session, connection) {
+            self.message(x);
+        }
+        let hook = match phase.kind {
+            PomodoroKind::Work => Hook::WorkFinished,
+            PomodoroKind::Break => Hook::BreakFinished,
+        };
+        self.run_hook(hook, Some(phase), Some((ended_at, completed)));
     }
-}
 
-fn save_log(
-    commands_num: &mut i32,
-    count_time: &mut NaiveTime,
-    last_command: PomodoroCommands,
-    task_id: &i32,
-    line_count: i32,
-    clearing: &bool,
-) {
-    let mut repeat_question = true;
-    while repeat_question {
-        match last_command {
-            PomodoroCommands::Work => {
-                print!("Save log \"pause\"? [yes/no]: ");
+    /// Runs the command of the hook in the config, if any, in the background with `sh -c`. The
+    /// task and the phase are described in `WORKFLOW_*` environment variables and the output of
+    /// the command is discarded, so as not to break the screen.
+    fn run_hook(&mut self, hook: Hook, phase: Option<&Phase>, end: Option<(NaiveDateTime, bool)>) {
+        let Some(command) = hook.command() else {
+            return;
+        };
+
+        let mut child = process::Command::new("sh");
+        child
+            .arg("-c")
+            .arg(command)
+            .env("WORKFLOW_EVENT", hook.name())
+            .env("WORKFLOW_TASK_ID", self.task.task_id.to_string())
+            .env("WORKFLOW_TASK_NAME", &self.task.task_name)
+            .env("WORKFLOW_PROJECT_ID", self.task.project_id.to_string())
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        if let Some(phase) = phase {
+            let ended_at = end.map_or_else(config::now, |(x, _)| x);
+            let actual = (ended_at - phase.started_at).num_seconds().max(0);
+            child
+                .env("WORKFLOW_PHASE", phase.kind.as_str())
+                .env("WORKFLOW_PHASE_TITLE", &phase.title)
+                .env(
+                    "WORKFLOW_PLANNED_SECONDS",
+                    phase.planned.num_seconds().to_string(),
+                )
+                .env("WORKFLOW_ACTUAL_SECONDS", actual.to_string());
+        }
+        if let Some((_, completed)) = end {
+            child.env("WORKFLOW_COMPLETED", completed.to_string());
+        }
+        if let Some(cycle) = self.cycle {
+            child
+                .env("WORKFLOW_ROUND", self.round.to_string())
+                .env("WORKFLOW_ROUNDS", cycle.rounds.to_string());
+        }
+
+        match child.spawn() {
+            Ok(mut x) => {
+                thread::spawn(move || x.wait());
             }
-            PomodoroCommands::Pause => {
-                print!("Save log \"resume\"? [yes/no]: ");
+            Err(x) => self.message(format!("Error running the {} hook: {}", hook.name(), x)),
+        }
+    }
+
+    /// Saves the phase counted down at the moment, `completed` if its clock ran down, and goes
+    /// on to the next phase of the cycle, or asks about the log to write in the manual mode.
+    fn finish_phase(&mut self, completed: bool, connection: &mut DbConnection) {
+        let Some(phase) = self.phase.take() else {
+            return;
+        };
+        self.save(&phase, completed, config::now(), connection);
+        self.timer_changed = true;
+        self.message(format!(
+            "{} {}",
+            phase.title,
+            if completed { "finished" } else { "skipped" }
+        ));
+
+        match (self.cycle, phase.kind) {
+            (Some(_), PomodoroKind::Work) => {
+                self.start_cycle_phase(PomodoroKind::Break, connection)
             }
-            _ => {
-                return;
+            (Some(_), PomodoroKind::Break) => {
+                self.round += 1;
+                self.start_cycle_phase(PomodoroKind::Work, connection);
             }
+            (None, PomodoroKind::Work) => self.question = Some(LogType::Pause),
+            (None, PomodoroKind::Break) => self.question = Some(LogType::Resume),
         }
+        self.refresh_stats(connection);
+    }
 
-        io::stdout().flush().unwrap();
-        let mut command = String::new();
-        io::stdin()
-            .read_line(&mut command)
-            .expect("Failed to read line");
-        repeat_question = false;
-        *commands_num += 1;
-
-        match PomodoroCommands::from(command) {
-            PomodoroCommands::Yes => match last_command {
-                PomodoroCommands::Work => {
-                    let lines = logs::add_log_by_id(Commands::Pause, task_id);
-                    *commands_num += lines as i32 + 1;
+    /// Finishes the phase of a resumed timer whose deadline passed while no terminal showed it,
+    /// as it would have finished at the deadline: the work is paused then, in cycle mode or if
+    /// one agrees, and only then the next phase begins.
+    fn finish_expired(
+        &mut self,
+        phase: Phase,
+        deadline: NaiveDateTime,
+        connection: &mut DbConnection,
+    ) {
+        self.save(&phase, true, deadline, connection);
+        self.message(format!(
+            "{} finished at {}",
+            phase.title,
+            deadline.format(&config::get().date_format)
+        ));
+
+        let working =
+            phase.kind == PomodoroKind::Work && working_at(self.task.task_id, deadline, connection);
+        match (self.cycle, phase.kind) {
+            (Some(_), PomodoroKind::Work) => {
+                if working {
+                    self.add_log(LogType::Pause, deadline, connection);
                 }
-                PomodoroCommands::Pause => {
-                    let lines = logs::add_log_by_id(Commands::Resume, task_id);
-                    *commands_num += lines as i32 + 1;
+                self.start_cycle_phase(PomodoroKind::Break, connection);
+            }
+            (Some(_), PomodoroKind::Break) => {
+                self.round += 1;
+                self.start_cycle_phase(PomodoroKind::Work, connection);
+            }
+            (None, PomodoroKind::Work) => {
+                let question = format!(
+                    "Pause the task at {}? [yes/no]: ",
+                    deadline.format(&config::get().date_format)
+                );
+                if working && ask(&question) {
+                    self.add_log(LogType::Pause, deadline, connection);
                 }
-                _ => (),
-            },
-            PomodoroCommands::No => (),
-            _ => {
-                println!("Wrong command, write yes or no!");
-                io::stdout().flush().unwrap();
-                *commands_num += 1;
-                repeat_question = true;
             }
+            (None, PomodoroKind::Break) => self.question = Some(LogType::Resume),
         }
-        let size = terminal_size().unwrap().1 as i32;
-        if *clearing && commands_num > &mut (size - line_count) {
-            clear_terminal(commands_num, line_count);
+        self.refresh_stats(connection);
+    }
+
+    /// Starts the work or break of the current round, writing the resume or pause log unless
+    /// the task is in that state already.
+    fn start_cycle_phase(&mut self, kind: PomodoroKind, connection: &mut DbConnection) {
+        let Some(cycle) = self.cycle else {
+            return;
+        };
+        let position = (self.round - 1) % cycle.rounds + 1;
+        let (log_type, name, length) = match kind {
+            PomodoroKind::Work => (LogType::Resume, "work", cycle.work),
+            PomodoroKind::Break if position == cycle.rounds => {
+                (LogType::Pause, "long break", cycle.long_break)
+            }
+            PomodoroKind::Break => (LogType::Pause, "break", cycle.short_break),
+        };
+        if !self.log_phase(log_type, connection) {
+            return;
         }
-        let time_str: String = count_time.format("%H:%M:%S").to_string();
-        let mut stdout: io::Stdout = stdout();
-        stdout.execute(cursor::MoveTo(0, 0)).unwrap();
-        let time_str: String = to_string(&map_block(&to_block(&time_str), red));
 
-        println!("{}", red(&time_str));
-        stdout
-            .execute(cursor::MoveDown(*commands_num as u16))
-            .unwrap();
+        let title = format!(
+            "Round {} ({}/{}): {}",
+            self.round, position, cycle.rounds, name
+        );
+        self.message(format!(
+            "{} until {}",
+            title,
+            (config::now() + length).format("%H:%M")
+        ));
+        let phase = Phase::new(kind, title, length);
+        if kind == PomodoroKind::Work {
+            self.run_hook(Hook::WorkStarted, Some(&phase), None);
+        }
+        self.phase = Some(phase);
+        self.timer_changed = true;
     }
-}
 
-fn action_commands(
-    commands_num: &mut i32,
-    count_time: &mut NaiveTime,
-    line_count: i32,
-    mut task_id: i32,
-    clearing: &bool,
-) -> PomodoroCommands {
-    let mut repeat_question = true;
-    let time_regex = Regex::new(r"^\d+:\d+:\d+$").unwrap();
-    let mut last_command = PomodoroCommands::NoSuchCommand;
-    while repeat_question {
-        print!(">> ");
-        io::stdout().flush().unwrap();
-        let mut command = String::new();
-        io::stdin()
-            .read_line(&mut command)
-            .expect("Failed to read line");
-        let args: Vec<&str> = command.split_whitespace().collect();
-
-        repeat_question = false;
-        // *commands_num += 1;
-        last_command = PomodoroCommands::from(args.clone()[0].to_string());
-
-        match PomodoroCommands::from(args.clone()[0].to_string()) {
-            PomodoroCommands::Work => {
-                if action_possible(Commands::Resume, &task_id, commands_num) {
-                    let lines = logs::add_log_by_id(Commands::Resume, &task_id);
-                    *commands_num += lines as i32 + 1;
-                } else {
-                    println!("Continuing a previously started work");
-                    io::stdout().flush().unwrap();
-                    let stats = db_operations::stats::get_stats(&[]);
-                    let lines = stats::display_content(
-                        stats,
-                        stats::PrintMode::ConcreteTasks,
-                        Some(vec![task_id]),
-                    );
-                    io::stdout().flush().unwrap();
-
-                    *commands_num += lines as i32 + 2;
+    /// Writes the log beginning a phase of the cycle, unless the task is in that state already.
+    /// Returns false when the task isn't in progress anymore.
+    fn log_phase(&mut self, log_type: LogType, connection: &mut DbConnection) -> bool {
+        let recent_log =
+            match db_operations::logs::get_recent_log(self.task.task_id, true, connection) {
+                Ok(x) => x.map(|x| x.log_type),
+                Err(x) => {
+                    self.message(x);
+                    return false;
                 }
+            };
 
-                if args.len() >= 2 {
-                    if !time_regex.is_match(args[1]) {
-                        println!("Time format should be HH:MM:SS");
-                        repeat_question = true;
-                        *commands_num += 1;
-                    } else {
-                        *count_time = NaiveTime::parse_from_str(args[1], "%H:%M:%S").unwrap();
-                    }
-                } else {
-                    *count_time = NaiveTime::parse_from_str("00:00:05", "%H:%M:%S").unwrap();
-                }
+        match (recent_log, log_type) {
+            (None | Some(LogType::End), _) => {
+                self.message("The task is not in progress anymore, quit with q");
+                false
             }
-            PomodoroCommands::Pause => {
-                if action_possible(Commands::Pause, &task_id, commands_num) {
-                    let lines = logs::add_log_by_id(Commands::Pause, &task_id);
-                    *commands_num += lines as i32 + 1;
-                } else {
-                    println!("Continuing a previously started pause");
-                    io::stdout().flush().unwrap();
-                    let stats = db_operations::stats::get_stats(&[]);
-                    let lines = stats::display_content(
-                        stats,
-                        stats::PrintMode::ConcreteTasks,
-                        Some(vec![task_id]),
-                    );
-                    io::stdout().flush().unwrap();
-
-                    *commands_num += lines as i32 + 2;
-                }
+            (Some(LogType::Pause), LogType::Resume)
+            | (Some(LogType::Begin | LogType::Resume), LogType::Pause) => {
+                self.add_log(log_type, config::now(), connection)
+            }
+            _ => true,
+        }
+    }
 
-                if args.len() >= 2 {
-                    if !time_regex.is_match(args[1]) {
-                        println!("Time format should be HH:MM:SS");
-                        *commands_num += 1;
-                        repeat_question = true;
-                    } else {
-                        *count_time = NaiveTime::parse_from_str(args[1], "%H:%M:%S").unwrap();
-                    }
-                } else {
-                    *count_time = NaiveTime::parse_from_str("00:05:00", "%H:%M:%S").unwrap();
+    /// Starts counting down `length` given as HH:MM:SS, or the length in the config, writing the
+    /// resume log before work and the pause log before a break.
+    fn start_manual_phase(
+        &mut self,
+        kind: PomodoroKind,
+        length: Option<&&str>,
+        connection: &mut DbConnection,
+    ) {
+        let (log_type, name, default) = match kind {
+            PomodoroKind::Work => (LogType::Resume, "work", config::get().pomodoro_work),
+            PomodoroKind::Break => (LogType::Pause, "pause", config::get().pomodoro_pause),
+        };
+        let length = match length.map(|x| NaiveTime::parse_from_str(x, "%H:%M:%S")) {
+            None => default - NaiveTime::MIN,
+            Some(Ok(x)) if x != NaiveTime::MIN => x - NaiveTime::MIN,
+            Some(Ok(_)) => return self.message("Time should be longer than zero"),
+            Some(Err(_)) => return self.message("Time format should be HH:MM:SS"),
+        };
+
+        if let Some(phase) = self.phase.take() {
+            self.save(&phase, false, config::now(), connection);
+        }
+        self.question = None;
+        if !self.add_log(log_type, config::now(), connection) {
+            self.message(format!("Continuing a previously started {}", name));
+        }
+        self.refresh_stats(connection);
+
+        let title = format!("{} {}", name, (NaiveTime::MIN + length).format("%H:%M:%S"));
+        self.message(format!(
+            "{} until {}",
+            title,
+            (config::now() + length).format("%H:%M")
+        ));
+        let phase = Phase::new(kind, title, length);
+        if kind == PomodoroKind::Work {
+            self.run_hook(Hook::WorkStarted, Some(&phase), None);
+        }
+        self.phase = Some(phase);
+        self.timer_changed = true;
+    }
+
+    fn change_task(&mut self, task: &str, connection: &mut DbConnection) {
+        let task = match tasks::find_task(task, connection) {
+            Ok(x) => x,
+            Err(x) => return self.message(x),
+        };
+        if let Err(x) = pomodoro_possible(task.task_id, connection) {
+            self.message(x);
+            return self.message("Impossible to change task");
+        }
+
+        if let Some(phase) = self.phase.take() {
+            self.save(&phase, false, config::now(), connection);
+        }
+        self.question = None;
+        self.timer_changed = true;
+        self.message(format!(
+            "Changing task to {} \"{}\"",
+            task.task_id, task.task_name
+        ));
+        self.task = task;
+        self.refresh_stats(connection);
+        if self.cycle.is_some() {
+            self.start_cycle_phase(PomodoroKind::Work, connection);
+        }
+    }
+
+    /// Writes the log for the task now, reporting it in the messages. Returns false when the
+    /// log couldn't be written.
+    fn add_log(
+        &mut self,
+        log_type: LogType,
+        date: NaiveDateTime,
+        connection: &mut DbConnection,
+    ) -> bool {
+        let date_format = &config::get().date_format;
+        match db_operations::logs::add_log(
+            self.task.task_id,
+            log_type,
+            date,
+            config::get().one_active_task,
+            connection,
+        ) {
+            Ok((_, new_log, paused)) => {
+                for x in paused {
+                    self.message(format!(
+                        "Paused task {} at {}",
+                        x.task_id,
+                        x.date.format(date_format)
+                    ));
                 }
+                self.message(format!(
+                    "Saved log \"{}\" at {}",
+                    log_name(new_log.log_type),
+                    new_log.date.format(date_format)
+                ));
+                true
             }
-            PomodoroCommands::Exit => process::exit(0),
-            PomodoroCommands::CurrentTask => {
-                let stats = db_operations::stats::get_stats(&[]);
-                let lines = stats::display_content(
-                    stats,
-                    stats::PrintMode::ConcreteTasks,
-                    Some(vec![task_id]),
-                );
-                *commands_num += lines as i32;
-            }
-            PomodoroCommands::ChangeTask => {
-                repeat_question = true;
-
-                if args.len() < 2 {
-                    println!("No task id!");
-                    *commands_num += 1;
-                } else {
-                    let new_task_id = args[1].parse::<i32>();
-                    match new_task_id {
-                        Ok(x) => {
-                            match db_operations::tasks::find_task_by_id(&x) {
-                                Ok(Some(_)) => {
-                                    if !pomodoro_possible(Commands::Pause, &x) {
-                                        println!("Impossible to change task");
-                                        io::stdout().flush().unwrap();
-                                    } else {
-                                        println!("Changing task_id to {}", x);
-                                        io::stdout().flush().unwrap();
-
-                                        task_id = x;
-                                    }
-                                }
-                                Ok(None) => {
-                                    println!("No such task!");
-                                    io::stdout().flush().unwrap();
-
-                                    repeat_question = true;
-                                }
-                                Err(x) => {
-                                    println!("{}", x);
-                                    io::stdout().flush().unwrap();
-
-                                    repeat_question = true;
-                                }
-                            }
-                            *commands_num += 1;
-                        }
-                        Err(_) => {
-                            io::stdout().flush().unwrap();
-                            println!("Couldn't parse task id")
-                        }
-                    }
-                }
+            Err(x) => {
+                self.message(x.to_string());
+                false
             }
-            PomodoroCommands::Clear => {
-                clear_terminal(commands_num, line_count);
-                repeat_question = true;
+        }
+    }
+
+    fn draw<B: Backend>(&self, f: &mut Frame<B>) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Length(9),
+                    Constraint::Min(5),
+                    Constraint::Length(3),
+                ]
+                .as_ref(),
+            )
+            .split(f.size());
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+            .split(chunks[1]);
+
+        self.draw_clock(f, chunks[0]);
+        self.draw_messages(f, panes[0]);
+        self.draw_stats(f, panes[1]);
+        self.draw_prompt(f, chunks[2]);
+    }
+
+    /// The big clock with the time left: green during work, yellow during a break and red
+    /// while stopped.
+    fn draw_clock<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let (title, remaining, color) = match &self.phase {
+            None => ("Pomodoro", Duration::ZERO, Color::Red),
+            Some(x) if x.running_since.is_none() => (x.title.as_str(), x.remaining(), Color::Red),
+            Some(x) if x.kind == PomodoroKind::Work => {
+                (x.title.as_str(), x.remaining(), Color::Green)
             }
-            _ => {
-                println!("Wrong command!");
-                io::stdout().flush().unwrap();
-                *commands_num += 2;
-                repeat_question = true;
+            Some(x) => (x.title.as_str(), x.remaining(), Color::Yellow),
+        };
+
+        // Rounded up, so that the clock shows zero only once it has run down
+        let seconds = (remaining.as_millis() as u64).div_ceil(1000);
+        let time = if seconds < 3600 {
+            format!("{:02}:{:02}", seconds / 60, seconds % 60)
+        } else {
+            format!(
+                "{:02}:{:02}:{:02}",
+                seconds / 3600,
+                seconds / 60 % 60,
+                seconds % 60
+            )
+        };
+        let clock = Paragraph::new(Text::styled(
+            to_block_string(&time),
+            Style::default().fg(color),
+        ))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(clock, area);
+    }
+
+    /// The messages, scrolled to their end unless scrolled up with the arrows.
+    fn draw_messages<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let height = area.height.saturating_sub(2) as usize;
+        let end = self
+            .messages
+            .len()
+            .saturating_sub(self.scroll)
+            .max(height.min(self.messages.len()));
+        let lines: Vec<Spans> = self.messages[end.saturating_sub(height)..end]
+            .iter()
+            .map(|x| Spans::from(x.as_str()))
+            .collect();
+
+        let messages =
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Messages"));
+        f.render_widget(messages, area);
+    }
+
+    fn draw_stats<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let lines: Vec<Spans> = self
+            .stats
+            .iter()
+            .map(|(name, value)| {
+                Spans::from(vec![
+                    Span::styled(format!("{:<15}", name), Style::default().fg(Color::Cyan)),
+                    Span::raw(value.as_str()),
+                ])
+            })
+            .collect();
+
+        let stats =
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Task"));
+        f.render_widget(stats, area);
+    }
+
+    /// The command being typed, the question about the log or the keys to press.
+    fn draw_prompt<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let text = match (&self.input, self.question) {
+            (Some(input), _) => {
+                f.set_cursor(area.x + 2 + input.chars().count() as u16, area.y + 1);
+                format!(":{}", input)
             }
-        }
-        let size = terminal_size().unwrap().1 as i32;
-        if *clearing && commands_num > &mut (size - line_count) {
-            clear_terminal(commands_num, line_count);
-        }
-        let time_str: String = count_time.format("%H:%M:%S").to_string();
-        let mut stdout: io::Stdout = stdout();
-        stdout.execute(cursor::MoveTo(0, 0)).unwrap();
-        let time_str: String = to_string(&map_block(&to_block(&time_str), red));
+            (None, Some(log_type)) => format!("Save log \"{}\"? [y/n]", log_name(log_type)),
+            (None, None) => "space stop/run the clock, s skip, q quit, \
+                ↑/↓ scroll, : command (work/pause [HH:MM:SS], task ID/NAME, clear)"
+                .to_string(),
+        };
 
-        println!("{}", red(&time_str));
-        stdout
-            .execute(cursor::MoveDown(*commands_num as u16))
-            .unwrap();
+        let prompt = Paragraph::new(text).block(Block::default().borders(Borders::ALL));
+        f.render_widget(prompt, area);
     }
-    last_command
 }
 
-fn clear_terminal(commands_num: &mut i32, line_count: i32) {
-    let mut stdout = stdout();
-    stdout.execute(cursor::MoveTo(0, 0)).unwrap();
-    stdout.execute(cursor::MoveToColumn(0)).unwrap();
-    stdout
-        .execute(cursor::MoveDown((line_count - 1) as u16))
-        .unwrap();
-    stdout
-        .execute(terminal::Clear(terminal::ClearType::All))
-        .unwrap();
-    *commands_num = 2;
+fn log_name(log_type: LogType) -> &'static str {
+    match log_type {
+        LogType::Begin => "begin",
+        LogType::Pause => "pause",
+        LogType::Resume => "resume",
+        LogType::End => "end",
+    }
 }
 
-pub fn action_possible(log_type: Commands, num: &i32, commands_num: &mut i32) -> bool {
-    let recent_log = db_operations::logs::get_recent_log(*num, true);
+fn is_stale(timer: &PomodoroTimer) -> bool {
+    (config::now() - timer.updated_at)
+        .to_std()
+        .is_ok_and(|x| x > STALE_AFTER)
+}
 
-    match recent_log {
+/// Checks the timer left by the user's previous pomodoro mode. A timer still saved regularly
+/// belongs to a mode running in another terminal, and then another one can't be started. A stale
+/// timer is discarded, offering to pause its task at the timer's deadline first. Returns false
+/// when the pomodoro mode can't be started.
+fn check_timer(connection: &mut DbConnection) -> bool {
+    let timer = match db_operations::pomodoros::get_timer(&config::get().username, connection) {
+        Ok(Some(x)) => x,
+        Ok(None) => return true,
         Err(x) => {
             println!("{}", x);
-            io::stdout().flush().unwrap();
-        }
-        Ok(None) => {
-            if log_type != (Commands::Begin) {
-                println!("First begin the task, then perform other operations!");
-                io::stdout().flush().unwrap();
-            }
-        }
-        Ok(Some(x)) if x.log_type == Commands::End.to_string() => {
-            println!("Task has been ended!");
-            io::stdout().flush().unwrap();
+            return false;
         }
+    };
+    if !is_stale(&timer) {
+        println!(
+            "A pomodoro of task {} is running in another terminal",
+            timer.task_id
+        );
+        return false;
+    }
 
-        Ok(Some(x))
-            if x.log_type == Commands::Pause.to_string() && log_type == (Commands::Pause) =>
-        {
-            println!("Task has already been paused");
-            io::stdout().flush().unwrap();
-        }
+    let date_format = &config::get().date_format;
+    match timer.deadline {
+        Some(x) => println!(
+            "The pomodoro of task {} was left in a closed terminal: {} until {}",
+            timer.task_id,
+            timer.title,
+            x.format(date_format)
+        ),
+        None => println!(
+            "The pomodoro of task {} was left in a closed terminal: {}, stopped with {} left",
+            timer.task_id,
+            timer.title,
+            (NaiveTime::MIN + chrono::Duration::seconds(timer.remaining_seconds.into()))
+                .format("%H:%M:%S")
+        ),
+    }
 
-        Ok(Some(x))
-            if x.log_type != Commands::Pause.to_string() && log_type == (Commands::Resume) =>
-        {
-            println!("Pause task before you resume it");
-            io::stdout().flush().unwrap();
+    let ended_at = stale_end(&timer);
+    if timer.kind == PomodoroKind::Work && working_at(timer.task_id, ended_at, connection) {
+        println!("To continue it run pomodoro --resume, press ctrl + c to keep it until then");
+        if ask(&format!(
+            "Pause the task at {}? [yes/no]: ",
+            ended_at.format(date_format)
+        )) {
+            let _ = logs::add_log_by_id(LogType::Pause, &timer.task_id, Some(ended_at), connection);
         }
-
-        _ => return true,
+    } else {
+        println!("Discarding it, to continue it instead run pomodoro --resume");
     }
+    discard_timer(&timer, connection);
+    true
+}
 
-    *commands_num += 1;
-    false
+/// Whether the task was begun or resumed at `date` and is still in progress.
+fn working_at(task_id: i32, date: NaiveDateTime, connection: &mut DbConnection) -> bool {
+    matches!(
+        db_operations::logs::get_recent_log(task_id, true, connection),
+        Ok(Some(x)) if matches!(x.log_type, LogType::Begin | LogType::Resume) && x.date <= date
+    )
 }
 
-fn pomodoro_possible(log_type: Commands, num: &i32) -> bool {
-    let recent_log = db_operations::logs::get_recent_log(*num, true);
+/// When the work or break of a stale timer ended: at the deadline if it has passed, or now, or
+/// when the timer was saved last if its clock was stopped.
+fn stale_end(timer: &PomodoroTimer) -> NaiveDateTime {
+    timer
+        .deadline
+        .map_or(timer.updated_at, |x| x.min(config::now()))
+}
 
-    match recent_log {
-        Err(x) => {
-            println!("{}", x);
-        }
-        Ok(None) => {
-            if log_type != (Commands::Begin) {
-                println!("First begin the task, then perform other operations!");
-            }
+/// Saves the session of a stale timer, completed if its deadline has passed, and deletes the
+/// timer.
+fn discard_timer(timer: &PomodoroTimer, connection: &mut DbConnection) {
+    let completed = timer.deadline.is_some_and(|x| x <= config::now());
+    let session = Phase::from_timer(timer).session(timer.task_id, completed, stale_end(timer));
+    if let Err(x) = db_operations::pomodoros::add_session(&session, connection) {
+        println!("{}", x);
+    }
+    if let Err(x) =
+        db_operations::pomodoros::delete_timer(&timer.username, &timer.owner, connection)
+    {
+        println!("{}", x);
+    }
+}
+
+/// Asks a yes or no question until one of them is answered; no when the input ends.
+fn ask(question: &str) -> bool {
+    loop {
+        print!("{}", question);
+        io::stdout().flush().unwrap();
+        let mut answer = String::new();
+        match io::stdin().read_line(&mut answer) {
+            Ok(0) | Err(_) => return false,
+            Ok(_) => (),
         }
-        Ok(Some(x)) if x.log_type == Commands::End.to_string() => {
-            println!("Task has been ended!");
+        match answer.trim() {
+            "yes" => return true,
+            "no" => return false,
+            _ => println!("Wrong command, write yes or no!"),
         }
-
-        _ => return true,
     }
-    io::stdout().flush().unwrap();
-    false
+}
+
+/// Pomodoro mode needs a task in progress, i.e. one that could be ended right now.
+fn pomodoro_possible(task_id: i32, connection: &mut DbConnection) -> Result<(), String> {
+    let recent_log = db_operations::logs::get_recent_log(task_id, true, connection)?;
+    db_operations::logs::validate_transition(recent_log.as_ref(), LogType::End)
+        .map_err(|x| x.to_string())
 }
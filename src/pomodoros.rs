@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+
+use chrono::{Duration, NaiveDate};
+use comfy_table::modifiers::UTF8_ROUND_CORNERS;
+use comfy_table::presets::UTF8_FULL;
+use comfy_table::*;
+use serde::Serialize;
+use workflow::models::{PomodoroKind, PomodoroSession};
+use workflow::DbConnection;
+
+use crate::db_operations;
+use crate::output::{self, as_seconds, Format};
+use crate::stats;
+
+/// A row of the pomodoro report: the rounds of work done in a task on a day, or in the whole
+/// range when `date` is missing.
+#[derive(Serialize)]
+struct PomodoroRow {
+    date: Option<NaiveDate>,
+    task_id: i32,
+    task_name: String,
+    completed: i32,
+    interrupted: i32,
+    /// Time the rounds of work actually took, including the interrupted ones.
+    #[serde(serialize_with = "as_seconds")]
+    worked: Duration,
+    breaks: i32,
+    /// Percentage of the rounds of work that were interrupted.
+    interruption_rate: Option<f64>,
+}
+
+impl PomodoroRow {
+    fn new(date: Option<NaiveDate>, task_id: i32, task_name: String) -> PomodoroRow {
+        PomodoroRow {
+            date,
+            task_id,
+            task_name,
+            completed: 0,
+            interrupted: 0,
+            worked: Duration::zero(),
+            breaks: 0,
+            interruption_rate: None,
+        }
+    }
+
+    fn add(&mut self, session: &PomodoroSession) {
+        match session.kind {
+            PomodoroKind::Work if session.completed => self.completed += 1,
+            PomodoroKind::Work => self.interrupted += 1,
+            PomodoroKind::Break => self.breaks += 1,
+        }
+        if session.kind == PomodoroKind::Work {
+            self.worked += Duration::seconds(i64::from(session.actual_seconds));
+            self.interruption_rate = Some(
+                f64::from(self.interrupted) * 100.0 / f64::from(self.completed + self.interrupted),
+            );
+        }
+    }
+}
+
+/// Displays the pomodoro rounds of every task on each day from `from` to `to`, followed by the
+/// totals per task for the whole range.
+pub fn display_pomodoros(
+    from: NaiveDate,
+    to: NaiveDate,
+    format: Format,
+    connection: &mut DbConnection,
+) {
+    let sessions = match db_operations::pomodoros::get_sessions(from, to, connection) {
+        Ok(x) => x,
+        Err(x) => return println!("{}", x),
+    };
+
+    let mut days: BTreeMap<(NaiveDate, i32), PomodoroRow> = BTreeMap::new();
+    let mut tasks: BTreeMap<i32, PomodoroRow> = BTreeMap::new();
+    let mut total = PomodoroRow::new(None, 0, "total".to_string());
+    for (session, task_name) in sessions.iter() {
+        let date = session.started_at.date();
+        days.entry((date, session.task_id))
+            .or_insert_with(|| PomodoroRow::new(Some(date), session.task_id, task_name.clone()))
+            .add(session);
+        tasks
+            .entry(session.task_id)
+            .or_insert_with(|| PomodoroRow::new(None, session.task_id, task_name.clone()))
+            .add(session);
+        total.add(session);
+    }
+    let rows: Vec<PomodoroRow> = days.into_values().chain(tasks.into_values()).collect();
+
+    if format != Format::Table {
+        return output::print_rows(&rows, format);
+    }
+    if rows.is_empty() {
+        return println!("You didn't do any pomodoros from {} to {}", from, to);
+    }
+
+    println!("From {} to {} you did the following pomodoros:", from, to);
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .apply_modifier(UTF8_ROUND_CORNERS)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec![
+            Cell::new("date")
+                .set_alignment(CellAlignment::Center)
+                .fg(Color::Cyan),
+            Cell::new("task_id")
+                .set_alignment(CellAlignment::Center)
+                .fg(Color::Cyan),
+            Cell::new("task_name")
+                .set_alignment(CellAlignment::Center)
+                .fg(Color::Cyan),
+            Cell::new("completed")
+                .set_alignment(CellAlignment::Center)
+                .fg(Color::Cyan),
+            Cell::new("interrupted")
+                .set_alignment(CellAlignment::Center)
+                .fg(Color::Cyan),
+            Cell::new("worked")
+                .set_alignment(CellAlignment::Center)
+                .fg(Color::Cyan),
+            Cell::new("breaks")
+                .set_alignment(CellAlignment::Center)
+                .fg(Color::Cyan),
+            Cell::new("interruption rate")
+                .set_alignment(CellAlignment::Center)
+                .fg(Color::Cyan),
+        ]);
+    for row in rows.into_iter().chain([total]) {
+        table.add_row(vec![
+            Cell::new(row.date.map_or("total".to_string(), |x| x.to_string()))
+                .set_alignment(CellAlignment::Center),
+            Cell::new(if row.task_id == 0 {
+                "total".to_string()
+            } else {
+                row.task_id.to_string()
+            })
+            .set_alignment(CellAlignment::Center),
+            Cell::new(row.task_name).set_alignment(CellAlignment::Center),
+            Cell::new(row.completed).set_alignment(CellAlignment::Center),
+            Cell::new(row.interrupted).set_alignment(CellAlignment::Center),
+            Cell::new(stats::format_duration(row.worked)).set_alignment(CellAlignment::Center),
+            Cell::new(row.breaks).set_alignment(CellAlignment::Center),
+            Cell::new(stats::format_percent(row.interruption_rate))
+                .set_alignment(CellAlignment::Center),
+        ]);
+    }
+    println!("{table}");
+}
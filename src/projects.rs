@@ -1,81 +1,190 @@
+use crate::output::{self, Format};
 use crate::stats;
-use crate::Commands;
-use chrono::NaiveDateTime;
+use serde::Serialize;
 use comfy_table::modifiers::UTF8_ROUND_CORNERS;
 use comfy_table::presets::UTF8_FULL;
 use comfy_table::*;
-use regex::Regex;
-use std::collections::HashMap;
-use std::process;
-use workflow::models::Task;
+use std::collections::{HashMap, HashSet};
+use workflow::lookup::{self, Match};
+use workflow::models::{LogType, Project, ProjectChanges};
+use workflow::DbConnection;
 
 use crate::db_operations;
 
-pub fn add_project(args: Vec<String>) {
-    if args.len() < 3 {
-        eprintln!("Too few args");
-        process::exit(-1);
+pub fn add_project(
+    project_name: &str,
+    planned_minutes: Option<i32>,
+    project_apps: &[String],
+    connection: &mut DbConnection,
+) {
+    println!("Project name: {}", project_name);
+
+    if let Err(x) = db_operations::projects::add_project(
+        project_name,
+        planned_minutes,
+        Some(project_apps).filter(|x| !x.is_empty()),
+        true,
+        connection,
+    ) {
+        println!("{}", x);
     }
-    let time_regex = Regex::new(r"^\d+:\d+:\d+$").unwrap();
-    let arg_regex = Regex::new(r"-.*").unwrap();
+}
+
+/// A project with one of its apps, if it has any.
+#[derive(Serialize)]
+struct ProjectAppEntry {
+    project_id: i32,
+    project_name: String,
+    app_id: Option<i32>,
+    app_name: Option<String>,
+}
 
-    if arg_regex.is_match(&args[2]) {
-        println!("First argument must be project name!");
-        process::exit(-1);
+/// A project with one of its tasks, if it has any.
+#[derive(Serialize)]
+struct ProjectTaskEntry {
+    project_id: i32,
+    project_name: String,
+    task_id: Option<i32>,
+    task_name: Option<String>,
+    planned_minutes: Option<i32>,
+}
+
+pub fn display_projects(include_archived: bool, format: Format, connection: &mut DbConnection) {
+    let stats = db_operations::stats::get_stats(&[], connection);
+    if include_archived {
+        stats::display_content(stats, stats::PrintMode::AllProjects, None, format, connection);
+        return;
     }
-    let project_name = &args[2];
-    println!("Project name: {}", project_name);
 
-    let mut time_planned: Option<&str> = None;
-    let mut project_apps: Option<&[String]> = None;
-
-    let mut i = 3;
-    while i < args.len() {
-        match &args[i][..] {
-            "-t" => {
-                i += 1;
-                if time_regex.is_match(&args[i]) {
-                    time_planned = Some(&args[i]);
-                } else {
-                    println!("Wrong time format!");
-                }
-                i += 1;
-            }
-            "-a" => {
-                i += 1;
-                if let None = project_apps {
-                    let j = i;
-                    while i < args.len() && !(arg_regex.is_match(&args[i])) {
-                        i += 1;
-                    }
-                    project_apps = Option::from(&args[j..i]);
-                }
-            }
+    // Archived projects are hidden after their stats are counted, but their tasks are left out of
+    // the total beforehand; archived tasks still count in their projects.
+    let archived_projects: HashSet<i32> = db_operations::projects::get_projects(connection)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|project| project.archived)
+        .map(|project| project.project_id)
+        .collect();
+    let stats = stats.map(|rows| {
+        rows.into_iter()
+            .filter(|(task, ..)| !archived_projects.contains(&task.project_id))
+            .collect()
+    });
+    stats::display_content(stats, stats::PrintMode::ActiveProjects, None, format, connection);
+}
 
-            _ => {
-                println!("Unknown argument '{}': try again", &args[i]);
-            }
-        }
+/// Finds the project given by id or name, printing a message when there is none or the name is
+/// ambiguous.
+pub fn resolve_project(arg: &str, connection: &mut DbConnection) -> Option<Project> {
+    find_project(arg, connection)
+        .map_err(|x| println!("{}", x))
+        .ok()
+}
+
+/// Like `resolve_project`, but returns the message instead of printing it.
+pub fn find_project(arg: &str, connection: &mut DbConnection) -> Result<Project, String> {
+    if let Ok(num) = arg.parse::<i32>() {
+        return match db_operations::projects::get_project_by_id(num, connection) {
+            Ok(Some(x)) => Ok(x),
+            Ok(None) => Err("No such project!".to_string()),
+            Err(x) => Err(x.to_string()),
+        };
     }
 
-    if let Err(x) =
-        db_operations::projects::add_project(project_name, time_planned, project_apps, true)
-    {
-        println!("{}", x);
+    let projects = match db_operations::projects::get_projects(connection) {
+        Ok(x) => x,
+        Err(_) => return Err("An error occured while fetching project".to_string()),
+    };
+    match lookup::find(arg, projects, |project| project.project_name.as_str()) {
+        Match::One(x) => Ok(x),
+        Match::Ambiguous(x) => Err(format!(
+            "Project \"{}\" is ambiguous, it may be:\n{}\nGive the project by id",
+            arg,
+            candidates_list(&x)
+        )),
+        Match::NoMatch(x) if x.is_empty() => Err("No such project!".to_string()),
+        Match::NoMatch(x) => Err(format!("No such project!\nDid you mean:\n{}", candidates_list(&x))),
+    }
+}
+
+fn candidates_list(projects: &[Project]) -> String {
+    projects
+        .iter()
+        .map(|project| format!("  {:>4}  {}", project.project_id, project.project_name))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub fn edit_project(
+    project: &str,
+    project_name: Option<&str>,
+    planned_minutes: Option<i32>,
+    connection: &mut DbConnection,
+) {
+    if project_name.is_none() && planned_minutes.is_none() {
+        println!("Nothing to change, use -n NAME or -t TIME");
+        return;
+    }
+
+    let Some(project) = resolve_project(project, connection) else {
+        return;
+    };
+    let changes = ProjectChanges {
+        project_name,
+        planned_minutes,
+        ..Default::default()
+    };
+    match db_operations::projects::edit_project(project.project_id, &changes, connection) {
+        Ok(x) => println!("Saved project {} \"{}\"", x.project_id, x.project_name),
+        Err(x) => println!("{}", x),
+    }
+}
+
+pub fn archive_project(project: &str, archived: bool, connection: &mut DbConnection) {
+    let Some(project) = resolve_project(project, connection) else {
+        return;
+    };
+    let changes = ProjectChanges {
+        archived: Some(archived),
+        ..Default::default()
+    };
+    match db_operations::projects::edit_project(project.project_id, &changes, connection) {
+        Ok(x) if archived => println!("Archived project \"{}\"", x.project_name),
+        Ok(x) => println!("Restored project \"{}\"", x.project_name),
+        Err(x) => println!("{}", x),
     }
 }
-pub fn display_projects() {
-    let _a = db_operations::projects::get_projects();
-    let stats: Result<Vec<(Task, Option<i32>, Option<String>, Option<NaiveDateTime>)>, &str> =
-        db_operations::stats::get_stats(&[]);
-    stats::display_content(stats, stats::PrintMode::AllProjects, None);
+
+pub fn delete_project(project: &str, cascade: bool, connection: &mut DbConnection) {
+    let Some(project) = resolve_project(project, connection) else {
+        return;
+    };
+    match db_operations::projects::delete_project(project.project_id, cascade, connection) {
+        Ok((tasks_num, logs_num)) => println!(
+            "Deleted project \"{}\" with {} tasks and {} logs",
+            project.project_name, tasks_num, logs_num
+        ),
+        Err(x) => println!("{}", x),
+    }
 }
 
-pub fn display_project_apps() {
-    let project_apps = db_operations::projects::get_apps_in_projects();
+pub fn display_project_apps(format: Format, connection: &mut DbConnection) {
+    let project_apps = db_operations::projects::get_apps_in_projects(connection);
 
-    let project_apps = project_apps.ok().unwrap_or(vec![]);
-    if project_apps.len() == 0 {
+    let project_apps = project_apps.ok().unwrap_or_default();
+    if format != Format::Table {
+        let rows: Vec<ProjectAppEntry> = project_apps
+            .into_iter()
+            .map(|(project, app_name, app_id)| ProjectAppEntry {
+                project_id: project.project_id,
+                project_name: project.project_name,
+                app_id,
+                app_name,
+            })
+            .collect();
+        return output::print_rows(&rows, format);
+    }
+
+    if project_apps.is_empty() {
         println!("No apps in the projects!");
 
         return;
@@ -188,89 +297,44 @@ pub fn display_project_apps() {
     println!("{}", table);
 }
 
-pub fn display_project_tasks(args: &[String]) {
-    let project_regex = Regex::new(r"\d+").unwrap();
-    let arg_regex: Regex = Regex::new(r"-.*").unwrap();
-    let mut i = 0;
-    let mut seeked_project_id: Option<Vec<i32>> = None;
-    let mut found_project = false;
-    let mut command: HashMap<String, bool> = HashMap::new();
-    while i < args.len() {
-        match &args[i][..] {
-            "-pr" => {
-                i += 1;
-                let mut project_ids = vec![];
-                if found_project {
-                    eprintln!("Error: Multiple usage of the same options");
-                    return;
-                }
-                while i < args.len() && !arg_regex.is_match(&args[i]) {
-                    if !project_regex.is_match(&args[i]) {
-                        println!("Project id should be integer!");
-                        return;
-                    }
-                    project_ids.push(args[i].parse::<i32>().expect("Couldn't parse project_id"));
-
-                    i += 1;
-                }
-
-                seeked_project_id = Some(project_ids);
-
-                found_project = true;
-            }
-            "-e" => {
-                if command.contains_key(&Commands::End.to_string())
-                    && !command.get(&Commands::End.to_string()).unwrap()
-                {
-                    println!("Cannot apply contradictory filters!");
-                    process::exit(-1);
-                }
-                command.insert(Commands::End.to_string(), true);
-                i += 1;
-            }
-            "-ne" => {
-                if command.contains_key(&Commands::End.to_string())
-                    && !!command.get(&Commands::End.to_string()).unwrap()
-                {
-                    println!("Cannot apply contradictory filters!");
-                    process::exit(-1);
-                }
-                command.insert(Commands::End.to_string(), false);
-                i += 1;
-            }
-            "-b" => {
-                if command.contains_key(&Commands::Begin.to_string())
-                    && !command.get(&Commands::Begin.to_string()).unwrap()
-                {
-                    println!("Cannot apply contradictory filters!");
-                    process::exit(-1);
-                }
-                command.insert(Commands::Begin.to_string(), true);
-                i += 1;
-            }
-            "-nb" => {
-                if command.contains_key(&Commands::Begin.to_string())
-                    && !!command.get(&Commands::Begin.to_string()).unwrap()
-                {
-                    println!("Cannot apply contradictory filters!");
-                    process::exit(-1);
-                }
-                command.insert(Commands::Begin.to_string(), false);
-                i += 1;
-            }
-            _ => {
-                println!("Unknown argument '{}': try again", &args[i]);
-                process::exit(-1);
-            }
-        }
+pub fn display_project_tasks(
+    seeked_project_id: Option<Vec<i32>>,
+    begun: Option<bool>,
+    ended: Option<bool>,
+    format: Format,
+    connection: &mut DbConnection,
+) {
+    let mut command: HashMap<LogType, bool> = HashMap::new();
+    if let Some(x) = begun {
+        command.insert(LogType::Begin, x);
+    }
+    if let Some(x) = ended {
+        command.insert(LogType::End, x);
     }
 
-    let project_tasks =
-        db_operations::projects::get_tasks_in_projects(seeked_project_id.clone(), command);
+    let project_tasks = db_operations::projects::get_tasks_in_projects(
+        seeked_project_id.clone(),
+        command,
+        connection,
+    );
+
+    let project_tasks = project_tasks.ok().unwrap_or_default();
+    if format != Format::Table {
+        let rows: Vec<ProjectTaskEntry> = project_tasks
+            .into_iter()
+            .map(|(project, task_name, planned_minutes, task_id)| ProjectTaskEntry {
+                project_id: project.project_id,
+                project_name: project.project_name,
+                task_id,
+                task_name,
+                planned_minutes,
+            })
+            .collect();
+        return output::print_rows(&rows, format);
+    }
 
-    let project_tasks = project_tasks.ok().unwrap_or(vec![]);
-    if project_tasks.len() == 0 {
-        if seeked_project_id.is_none() || seeked_project_id.clone().unwrap().len() > 0 {
+    if project_tasks.is_empty() {
+        if seeked_project_id.as_ref().is_none_or(|ids| !ids.is_empty()) {
             println!("No tasks in these projects!");
         } else {
             println!("No tasks in this project!");
@@ -302,7 +366,7 @@ pub fn display_project_tasks(args: &[String]) {
     let mut task_count = 0;
     let mut prev_color = Color::DarkGreen;
     let mut cur_color = Color::DarkCyan;
-    for (project, task_name, _planned_time, task_id) in project_tasks {
+    for (project, task_name, _planned_minutes, task_id) in project_tasks {
         if project.project_id != prev_project_id {
             if prev_project_id != 0 {
                 table.add_row(vec![
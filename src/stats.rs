@@ -1,70 +1,160 @@
-use chrono::Duration;
+use chrono::{Days, Duration};
 use std::cmp::max;
-use std::process;
-use workflow::models::{Project, Task};
+use workflow::intervals;
+use workflow::models::{LogType, Project, Task};
+use workflow::DbConnection;
 
 use crate::db_operations;
-use crate::db_operations::projects::{get_date_projects, get_projects};
-use crate::Commands;
+use crate::db_operations::projects::get_projects;
+use crate::db_operations::stats::TaskLogRow;
+use crate::output::{self, as_optional_seconds, as_seconds, Format};
+use serde::Serialize;
 use chrono::prelude::*;
 use comfy_table::modifiers::UTF8_ROUND_CORNERS;
 use comfy_table::presets::UTF8_FULL;
 use comfy_table::*;
 use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 
 pub enum PrintMode {
     All,
     Appearing,
     Project,
     AllProjects,
+    ActiveProjects,
     ConcreteTasks,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ProjectStats {
     project_id: i32,
     project_name: String,
     username: String,
-    planned_time: Option<String>,
+    #[serde(serialize_with = "as_optional_seconds")]
+    planned_time: Option<Duration>,
+    #[serde(serialize_with = "as_seconds")]
     total_time: Duration,
+    #[serde(serialize_with = "as_seconds")]
     total_worked: Duration,
     pause_num: i32,
+    #[serde(serialize_with = "as_seconds")]
     longest_pause: Duration,
+    #[serde(serialize_with = "as_seconds")]
     longest_work: Duration,
     total_tasks: i32,
     completed_tasks: i32,
+    archived: bool,
+    #[serde(serialize_with = "as_optional_seconds")]
+    remaining: Option<Duration>,
+    #[serde(serialize_with = "as_optional_seconds")]
+    overrun: Option<Duration>,
+    percent: Option<f64>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TaskStats {
     task_id: i32,
     project_id: i32,
     task_name: String,
     username: String,
-    planned_time: Option<String>,
+    #[serde(serialize_with = "as_optional_seconds")]
+    planned_time: Option<Duration>,
+    #[serde(serialize_with = "as_seconds")]
     total_time: Duration,
+    #[serde(serialize_with = "as_seconds")]
     total_worked: Duration,
     pause_num: i32,
+    #[serde(serialize_with = "as_seconds")]
     longest_pause: Duration,
+    #[serde(serialize_with = "as_seconds")]
     longest_work: Duration,
+    #[serde(serialize_with = "as_seconds")]
     since_last_log: Duration,
-    // percent: f32,
+    #[serde(serialize_with = "as_optional_seconds")]
+    remaining: Option<Duration>,
+    #[serde(serialize_with = "as_optional_seconds")]
+    overrun: Option<Duration>,
+    percent: Option<f64>,
 }
-pub fn display_stats(args: &[String]) {
-    let stats = db_operations::stats::get_stats(args);
-    display_content(stats, PrintMode::All, None);
+pub fn display_stats(format: Format, connection: &mut DbConnection) {
+    let stats = db_operations::stats::get_stats(&[], connection);
+    display_content(stats, PrintMode::All, None, format, connection);
 }
 pub fn display_content(
-    stats: Result<Vec<(Task, Option<i32>, Option<String>, Option<NaiveDateTime>)>, &str>,
+    stats: Result<Vec<TaskLogRow>, &str>,
     print_mode: PrintMode,
     concrete_tasks: Option<Vec<i32>>,
+    format: Format,
+    connection: &mut DbConnection,
 ) -> usize {
-    let all_projects = get_projects().ok();
+    let now = workflow::config::now();
+    display_range_content(
+        stats,
+        None,
+        Some(now),
+        print_mode,
+        concrete_tasks,
+        format,
+        connection,
+    )
+}
+
+/// The stats `display_content` shows for a task, as names and values, for displaying them
+/// elsewhere than in a table.
+pub fn task_summary(
+    task_id: i32,
+    connection: &mut DbConnection,
+) -> Result<Vec<(&'static str, String)>, &'static str> {
+    let stats = db_operations::stats::get_stats(&[], connection)?;
+    let all_projects = get_projects(connection).ok();
+    let (_, task_stats) =
+        get_stats_map(all_projects, Ok(stats), None, Some(workflow::config::now()));
+    let Some(task) = task_stats.into_iter().find(|x| x.task_id == task_id) else {
+        return Err("No such task!");
+    };
 
-    let (project_stats, task_stats) = get_stats_map(all_projects, stats);
+    Ok(vec![
+        ("task", format!("{} {}", task.task_id, task.task_name)),
+        ("planned time", format_optional_duration(task.planned_time)),
+        ("total time", format_duration(task.total_time)),
+        ("total worked", format_duration(task.total_worked)),
+        ("pause num", task.pause_num.to_string()),
+        ("longest pause", format_duration(task.longest_pause)),
+        ("longest work", format_duration(task.longest_work)),
+        ("since last log", format_duration(task.since_last_log)),
+        ("remaining", format_optional_duration(task.remaining)),
+        ("overrun", format_optional_duration(task.overrun)),
+        ("percent", format_percent(task.percent)),
+    ])
+}
+
+/// Like `display_content`, but counts only the work and pauses within `range` when given and
+/// the running intervals only up to `until` when given.
+fn display_range_content(
+    stats: Result<Vec<TaskLogRow>, &str>,
+    range: Option<&Range<NaiveDateTime>>,
+    until: Option<NaiveDateTime>,
+    print_mode: PrintMode,
+    concrete_tasks: Option<Vec<i32>>,
+    format: Format,
+    connection: &mut DbConnection,
+) -> usize {
+    let all_projects = get_projects(connection).ok();
+
+    let (project_stats, task_stats) = get_stats_map(all_projects, stats, range, until);
+    if format != Format::Table {
+        print_stats_rows(
+            project_stats,
+            task_stats,
+            print_mode,
+            concrete_tasks,
+            format,
+        );
+        return 0;
+    }
 
     let mut table = Table::new();
-    if let PrintMode::Project | PrintMode::AllProjects = print_mode {
+    if let PrintMode::Project | PrintMode::AllProjects | PrintMode::ActiveProjects = print_mode {
         table
             .load_preset(UTF8_FULL)
             .apply_modifier(UTF8_ROUND_CORNERS)
@@ -91,6 +181,15 @@ pub fn display_content(
                 Cell::new("total_worked")
                     .set_alignment(CellAlignment::Center)
                     .fg(Color::Cyan),
+                Cell::new("remaining")
+                    .set_alignment(CellAlignment::Center)
+                    .fg(Color::Cyan),
+                Cell::new("overrun")
+                    .set_alignment(CellAlignment::Center)
+                    .fg(Color::Cyan),
+                Cell::new("percent")
+                    .set_alignment(CellAlignment::Center)
+                    .fg(Color::Cyan),
             ]);
     } else if let PrintMode::ConcreteTasks = print_mode {
         table
@@ -131,6 +230,15 @@ pub fn display_content(
                 Cell::new("since_last_log")
                     .set_alignment(CellAlignment::Center)
                     .fg(Color::Cyan),
+                Cell::new("remaining")
+                    .set_alignment(CellAlignment::Center)
+                    .fg(Color::Cyan),
+                Cell::new("overrun")
+                    .set_alignment(CellAlignment::Center)
+                    .fg(Color::Cyan),
+                Cell::new("percent")
+                    .set_alignment(CellAlignment::Center)
+                    .fg(Color::Cyan),
             ]);
     } else {
         table
@@ -168,12 +276,21 @@ pub fn display_content(
                 Cell::new("longest work")
                     .set_alignment(CellAlignment::Center)
                     .fg(Color::Cyan),
+                Cell::new("remaining")
+                    .set_alignment(CellAlignment::Center)
+                    .fg(Color::Cyan),
+                Cell::new("overrun")
+                    .set_alignment(CellAlignment::Center)
+                    .fg(Color::Cyan),
+                Cell::new("percent")
+                    .set_alignment(CellAlignment::Center)
+                    .fg(Color::Cyan),
             ]);
     }
     let mut project_ids = HashSet::new();
 
     for task in task_stats {
-        if let PrintMode::Project | PrintMode::AllProjects = print_mode {
+        if let PrintMode::Project | PrintMode::AllProjects | PrintMode::ActiveProjects = print_mode {
         } else if let PrintMode::ConcreteTasks = print_mode {
             if let Some(ref x) = concrete_tasks {
                 if x.contains(&task.task_id) {
@@ -182,7 +299,7 @@ pub fn display_content(
                         Cell::new(task.project_id).set_alignment(CellAlignment::Center),
                         Cell::new(task.task_name.clone()).set_alignment(CellAlignment::Center),
                         Cell::new(task.username.clone()).set_alignment(CellAlignment::Center),
-                        Cell::new(task.planned_time.unwrap_or("null".to_string()))
+                        Cell::new(format_optional_duration(task.planned_time))
                             .set_alignment(CellAlignment::Center),
                         Cell::new(format!(
                             "{:02}:{:02}:{:02}",
@@ -221,6 +338,12 @@ pub fn display_content(
                                 - 60 * task.since_last_log.num_hours()
                         ))
                         .set_alignment(CellAlignment::Center),
+                        Cell::new(format_optional_duration(task.remaining))
+                            .set_alignment(CellAlignment::Center),
+                        Cell::new(format_optional_duration(task.overrun))
+                            .set_alignment(CellAlignment::Center),
+                        Cell::new(format_percent(task.percent))
+                            .set_alignment(CellAlignment::Center),
                     ]);
                 }
             }
@@ -230,7 +353,7 @@ pub fn display_content(
                 Cell::new(task.project_id).set_alignment(CellAlignment::Center),
                 Cell::new(task.task_name.clone()).set_alignment(CellAlignment::Center),
                 Cell::new(task.username.clone()).set_alignment(CellAlignment::Center),
-                Cell::new(task.planned_time.unwrap_or("null".to_string()))
+                Cell::new(format_optional_duration(task.planned_time))
                     .set_alignment(CellAlignment::Center),
                 Cell::new(format!(
                     "{:02}:{:02}:{:02}",
@@ -261,6 +384,11 @@ pub fn display_content(
                     task.longest_work.num_minutes() - 60 * task.longest_work.num_hours()
                 ))
                 .set_alignment(CellAlignment::Center),
+                Cell::new(format_optional_duration(task.remaining))
+                    .set_alignment(CellAlignment::Center),
+                Cell::new(format_optional_duration(task.overrun))
+                    .set_alignment(CellAlignment::Center),
+                Cell::new(format_percent(task.percent)).set_alignment(CellAlignment::Center),
             ]);
         }
 
@@ -293,7 +421,7 @@ pub fn display_content(
                 } else if b == &0 {
                     std::cmp::Ordering::Less
                 } else {
-                    a.cmp(&b)
+                    a.cmp(b)
                 }
             });
 
@@ -311,7 +439,7 @@ pub fn display_content(
                 } else if b == &0 {
                     std::cmp::Ordering::Less
                 } else {
-                    a.cmp(&b)
+                    a.cmp(b)
                 }
             });
 
@@ -322,8 +450,12 @@ pub fn display_content(
         }
 
         PrintMode::ConcreteTasks => {}
-        PrintMode::AllProjects => {
-            let mut vals: Vec<ProjectStats> = project_stats.values().cloned().collect();
+        PrintMode::AllProjects | PrintMode::ActiveProjects => {
+            let mut vals: Vec<ProjectStats> = project_stats
+                .values()
+                .filter(|x| !(x.archived && matches!(print_mode, PrintMode::ActiveProjects)))
+                .cloned()
+                .collect();
             vals.sort_by(|a, b| {
                 if a.project_id == 0 {
                     std::cmp::Ordering::Greater
@@ -344,6 +476,46 @@ pub fn display_content(
     table.lines().count()
 }
 
+/// Prints the stats selected by `print_mode` in a machine-readable format: project rows in the
+/// project modes and task rows otherwise, without the "total" rows of the tables.
+fn print_stats_rows(
+    project_stats: HashMap<i32, ProjectStats>,
+    task_stats: Vec<TaskStats>,
+    print_mode: PrintMode,
+    concrete_tasks: Option<Vec<i32>>,
+    format: Format,
+) {
+    let project_ids: HashSet<i32> = task_stats.iter().map(|task| task.project_id).collect();
+    let mut projects: Vec<ProjectStats> = project_stats
+        .into_values()
+        .filter(|project| project.project_id != 0)
+        .filter(|project| match print_mode {
+            PrintMode::Project => project_ids.contains(&project.project_id),
+            PrintMode::ActiveProjects => !project.archived,
+            _ => true,
+        })
+        .collect();
+    projects.sort_by_key(|project| project.project_id);
+
+    match print_mode {
+        PrintMode::Project | PrintMode::AllProjects | PrintMode::ActiveProjects => {
+            output::print_rows(&projects, format)
+        }
+        PrintMode::ConcreteTasks => {
+            let tasks: Vec<TaskStats> = task_stats
+                .into_iter()
+                .filter(|task| {
+                    concrete_tasks
+                        .as_ref()
+                        .is_some_and(|x| x.contains(&task.task_id))
+                })
+                .collect();
+            output::print_rows(&tasks, format)
+        }
+        PrintMode::All | PrintMode::Appearing => output::print_rows(&task_stats, format),
+    }
+}
+
 fn extend_table(table: &mut Table, project: &ProjectStats) {
     table.add_row(vec![
         Cell::new("total").set_alignment(CellAlignment::Center),
@@ -355,7 +527,7 @@ fn extend_table(table: &mut Table, project: &ProjectStats) {
         .set_alignment(CellAlignment::Center),
         Cell::new(project.project_name.clone()).set_alignment(CellAlignment::Center),
         Cell::new(project.username.clone()).set_alignment(CellAlignment::Center),
-        Cell::new(project.clone().planned_time.unwrap_or("null".to_string()))
+        Cell::new(format_optional_duration(project.planned_time))
             .set_alignment(CellAlignment::Center),
         Cell::new(format!(
             "{:02}:{:02}:{:02}",
@@ -386,6 +558,9 @@ fn extend_table(table: &mut Table, project: &ProjectStats) {
             project.longest_work.num_minutes() - 60 * project.longest_work.num_hours()
         ))
         .set_alignment(CellAlignment::Center),
+        Cell::new(format_optional_duration(project.remaining)).set_alignment(CellAlignment::Center),
+        Cell::new(format_optional_duration(project.overrun)).set_alignment(CellAlignment::Center),
+        Cell::new(format_percent(project.percent)).set_alignment(CellAlignment::Center),
     ]);
 }
 
@@ -397,11 +572,16 @@ fn extend_project_table(table: &mut Table, project: &ProjectStats) {
             project.project_id.to_string()
         })
         .set_alignment(CellAlignment::Center),
-        Cell::new(project.project_name.clone()).set_alignment(CellAlignment::Center),
+        Cell::new(if project.archived {
+            format!("{} (archived)", project.project_name)
+        } else {
+            project.project_name.clone()
+        })
+        .set_alignment(CellAlignment::Center),
         Cell::new(project.username.clone()).set_alignment(CellAlignment::Center),
         Cell::new(project.total_tasks).set_alignment(CellAlignment::Center),
         Cell::new(project.completed_tasks).set_alignment(CellAlignment::Center),
-        Cell::new(project.clone().planned_time.unwrap_or("null".to_string()))
+        Cell::new(format_optional_duration(project.planned_time))
             .set_alignment(CellAlignment::Center),
         Cell::new(format!(
             "{:02}:{:02}:{:02}",
@@ -410,12 +590,102 @@ fn extend_project_table(table: &mut Table, project: &ProjectStats) {
             project.total_worked.num_minutes() - project.total_worked.num_hours() * 60
         ))
         .set_alignment(CellAlignment::Center),
+        Cell::new(format_optional_duration(project.remaining)).set_alignment(CellAlignment::Center),
+        Cell::new(format_optional_duration(project.overrun)).set_alignment(CellAlignment::Center),
+        Cell::new(format_percent(project.percent)).set_alignment(CellAlignment::Center),
     ]);
 }
 
+/// Formats a duration as DAYS:HOURS:MINUTES, like the other durations in the tables.
+pub fn format_duration(duration: Duration) -> String {
+    format!(
+        "{:02}:{:02}:{:02}",
+        duration.num_days(),
+        duration.num_hours() - 24 * duration.num_days(),
+        duration.num_minutes() - 60 * duration.num_hours()
+    )
+}
+
+fn format_optional_duration(duration: Option<Duration>) -> String {
+    duration.map_or("null".to_string(), format_duration)
+}
+
+/// Formats planned time given in minutes, as stored in the database.
+pub fn format_minutes(minutes: Option<i32>) -> String {
+    format_optional_duration(minutes.map(|x| Duration::minutes(x.into())))
+}
+
+pub fn format_percent(percent: Option<f64>) -> String {
+    percent.map_or("null".to_string(), |x| format!("{:.0}%", x))
+}
+
+/// Compares the time worked with the planned time, returning the time remaining, the overrun
+/// and the percentage of the plan done; all of them are `None` when there is no plan.
+fn compare_with_plan(
+    planned: Option<Duration>,
+    worked: Duration,
+) -> (Option<Duration>, Option<Duration>, Option<f64>) {
+    let Some(planned) = planned else {
+        return (None, None, None);
+    };
+
+    let percent = if planned > Duration::zero() {
+        Some(worked.num_seconds() as f64 * 100.0 / planned.num_seconds() as f64)
+    } else {
+        None
+    };
+    (
+        Some(max(planned - worked, Duration::zero())),
+        Some(max(worked - planned, Duration::zero())),
+        percent,
+    )
+}
+
+/// Computes the stats of a task from its logs, ordered by date, counting only the work and
+/// pauses within `range` when given and the running interval up to `until` when given. Returns
+/// the stats with whether the task was ended, or `None` when there is a range and the task wasn't
+/// logged nor worked on within it.
+fn compute_task_stats(
+    task: &Task,
+    logs: &[(LogType, NaiveDateTime)],
+    range: Option<&Range<NaiveDateTime>>,
+    until: Option<NaiveDateTime>,
+) -> Option<(TaskStats, bool)> {
+    let summary = intervals::summarize(logs, range, until);
+    if range.is_some() && !summary.is_active() {
+        return None;
+    }
+
+    let now = workflow::config::now();
+    let task_stats = TaskStats {
+        task_id: task.task_id,
+        project_id: task.project_id,
+        task_name: task.task_name.clone(),
+        username: task.username.clone(),
+        planned_time: task.planned_minutes.map(|x| Duration::minutes(x.into())),
+        total_time: summary.total_time,
+        total_worked: summary.total_worked,
+        pause_num: summary.pause_num,
+        longest_pause: summary.longest_pause,
+        longest_work: summary.longest_work,
+        since_last_log: now.signed_duration_since(logs.last().map_or(now, |(_, date)| *date)),
+        remaining: None,
+        overrun: None,
+        percent: None,
+    };
+    Some((task_stats, summary.completed))
+}
+
+/// Returns the time from the start of the day `from` to the end of the day `to`.
+pub fn day_range(from: NaiveDate, to: NaiveDate) -> Range<NaiveDateTime> {
+    from.and_time(NaiveTime::MIN)..(to + Days::new(1)).and_time(NaiveTime::MIN)
+}
+
 fn get_stats_map(
     all_projects: Option<Vec<Project>>,
-    stats: Result<Vec<(Task, Option<i32>, Option<String>, Option<NaiveDateTime>)>, &str>,
+    stats: Result<Vec<TaskLogRow>, &str>,
+    range: Option<&Range<NaiveDateTime>>,
+    until: Option<NaiveDateTime>,
 ) -> (HashMap<i32, ProjectStats>, Vec<TaskStats>) {
     let mut project_stats = HashMap::new();
     let mut task_stats = vec![];
@@ -425,7 +695,7 @@ fn get_stats_map(
                 project_id: project.project_id,
                 project_name: project.project_name,
                 username: project.username,
-                planned_time: project.planned_time,
+                planned_time: project.planned_minutes.map(|x| Duration::minutes(x.into())),
                 total_time: Duration::new(0, 0).unwrap_or_default(),
                 total_worked: Duration::new(0, 0).unwrap_or_default(),
                 pause_num: 0,
@@ -433,6 +703,10 @@ fn get_stats_map(
                 longest_work: Duration::new(0, 0).unwrap_or_default(),
                 total_tasks: 0,
                 completed_tasks: 0,
+                archived: project.archived,
+                remaining: None,
+                overrun: None,
+                percent: None,
             };
 
             project_stats.insert(project.project_id, new_project_stats);
@@ -443,7 +717,7 @@ fn get_stats_map(
         project_id: 0,
         project_name: "null".to_string(),
         username: "null".to_string(),
-        planned_time: Some("null".to_string()),
+        planned_time: None,
         total_time: Duration::new(0, 0).unwrap_or_default(),
         total_worked: Duration::new(0, 0).unwrap_or_default(),
         pause_num: 0,
@@ -451,153 +725,41 @@ fn get_stats_map(
         longest_work: Duration::new(0, 0).unwrap_or_default(),
         total_tasks: 0,
         completed_tasks: 0,
+        archived: false,
+        remaining: None,
+        overrun: None,
+        percent: None,
     };
     project_stats.insert(0, total_stats.clone());
 
     match stats {
         Err(x) => println!("{}", x),
         Ok(result) => {
-            let mut i = 1;
-            let mut pause_num = 0;
-            let mut longest_pause = Duration::seconds(0);
-            let mut longest_work = Duration::seconds(0);
-            let mut begin;
-            let mut total_time = Duration::seconds(0);
-            let mut total_worked = Duration::seconds(0);
-            while i < result.len() {
-                pause_num = 0;
-                longest_pause = Duration::seconds(0);
-                longest_work = Duration::seconds(0);
-                begin = result[i - 1].3.unwrap_or_default();
-                total_time = Duration::seconds(0);
-                total_worked = Duration::seconds(0);
-                while i < result.len() && &result[i].0.task_id == &result[i - 1].0.task_id {
-                    if <Option<String> as Clone>::clone(&result[i].2).unwrap_or("".to_string())
-                        == Commands::Pause.to_string()
-                        || (<Option<String> as Clone>::clone(&result[i].2).unwrap_or_default()
-                            == Commands::End.to_string()
-                            && <Option<String> as Clone>::clone(&result[i - 1].2)
-                                .unwrap_or("".to_string())
-                                != Commands::Pause.to_string())
-                    {
-                        let slot = result[i]
-                            .3
-                            .unwrap_or_default()
-                            .signed_duration_since(result[i - 1].3.unwrap_or_default());
-                        longest_work = max(longest_work, slot);
-                        total_worked += slot;
-                        if <Option<String> as Clone>::clone(&result[i].2).unwrap_or("".to_string())
-                            == Commands::Pause.to_string()
-                        {
-                            pause_num += 1;
-                        }
-                    } else if <Option<String> as Clone>::clone(&result[i].2)
-                        .unwrap_or("".to_string())
-                        == Commands::Resume.to_string()
-                        || (<Option<String> as Clone>::clone(&result[i].2).unwrap_or_default()
-                            == Commands::End.to_string()
-                            && <Option<String> as Clone>::clone(&result[i - 1].2)
-                                .unwrap_or("".to_string())
-                                == Commands::Pause.to_string())
-                    {
-                        let slot = result[i]
-                            .3
-                            .unwrap_or_default()
-                            .signed_duration_since(result[i - 1].3.unwrap_or_default());
-                        longest_pause = max(longest_pause, slot);
-                    }
-
-                    total_time = result[i].3.unwrap_or_default().signed_duration_since(begin);
-                    i += 1;
-                }
-
-                if <Option<String> as Clone>::clone(&result[i - 1].2).unwrap_or_default()
-                    == Commands::End.to_string()
-                {
-                    let new_project_stats =
-                        project_stats.get_mut(&result[i - 1].0.project_id).unwrap();
-                    new_project_stats.completed_tasks += 1;
-                }
-
-                let new_task_stats = TaskStats {
-                    task_id: result[i - 1].0.task_id,
-                    project_id: result[i - 1].0.project_id.clone(),
-                    task_name: result[i - 1].clone().0.task_name,
-                    username: result[i - 1].0.username.clone(),
-                    planned_time: result[i - 1].clone().0.planned_time,
-                    total_time,
-                    total_worked,
-                    pause_num,
-                    longest_pause,
-                    longest_work,
-                    since_last_log: Local::now().naive_local().signed_duration_since(
-                        result[i - 1].3.unwrap_or(Local::now().naive_local()),
-                    ),
-                    // percent: 0.3,
+            for rows in result.chunk_by(|a, b| a.0.task_id == b.0.task_id) {
+                let task = &rows[0].0;
+                let logs: Vec<(LogType, NaiveDateTime)> =
+                    rows.iter().filter_map(|row| row.2.zip(row.3)).collect();
+                let Some((new_task_stats, completed)) =
+                    compute_task_stats(task, &logs, range, until)
+                else {
+                    continue;
                 };
 
-                task_stats.push(new_task_stats);
-
-                let new_project_stats = project_stats.get_mut(&result[i - 1].0.project_id).unwrap();
-                new_project_stats.total_time += total_time;
-                new_project_stats.total_worked += total_worked;
-                new_project_stats.pause_num += pause_num;
-                new_project_stats.longest_pause =
-                    max(new_project_stats.longest_pause, longest_pause);
-                new_project_stats.longest_work = max(longest_work, new_project_stats.longest_work);
-                new_project_stats.total_tasks += 1;
-
-                let total_stats = project_stats.get_mut(&0).unwrap();
-
-                total_stats.total_time += total_time;
-                total_stats.total_worked += total_worked;
-                total_stats.pause_num += pause_num;
-                total_stats.longest_pause = max(total_stats.longest_pause, longest_pause);
-                total_stats.longest_work = max(longest_work, total_stats.longest_work);
-                total_stats.total_tasks += 1;
-
-                i += 1;
-            }
-            if i <= result.len() {
-                let new_project_stats = project_stats.get_mut(&result[i - 1].0.project_id).unwrap();
-                new_project_stats.total_time += total_time;
-                new_project_stats.total_worked += total_worked;
-                new_project_stats.pause_num += pause_num;
-                new_project_stats.longest_pause =
-                    max(new_project_stats.longest_pause, longest_pause);
-                new_project_stats.longest_work = max(longest_work, new_project_stats.longest_work);
-                new_project_stats.total_tasks += 1;
-
-                let total_stats = project_stats.get_mut(&0).unwrap();
-                total_stats.total_time += total_time;
-                total_stats.total_worked += total_worked;
-                total_stats.pause_num += pause_num;
-                total_stats.longest_pause = max(total_stats.longest_pause, longest_pause);
-                total_stats.longest_work = max(longest_work, total_stats.longest_work);
-                total_stats.total_tasks += 1;
-
-                let new_task_stats = TaskStats {
-                    task_id: result[i - 1].0.task_id,
-                    project_id: result[i - 1].0.project_id.clone(),
-                    task_name: result[i - 1].clone().0.task_name,
-                    username: result[i - 1].0.username.clone(),
-                    planned_time: result[i - 1].clone().0.planned_time,
-                    total_time,
-                    total_worked,
-                    pause_num,
-                    longest_pause,
-                    longest_work,
-                    since_last_log: Local::now().naive_local().signed_duration_since(
-                        result[i - 1].3.unwrap_or(Local::now().naive_local()),
-                    ),
-                    // percent: 0.3,
-                };
-                if <Option<String> as Clone>::clone(&result[i - 1].2).unwrap_or_default()
-                    == Commands::End.to_string()
-                {
-                    let new_project_stats =
-                        project_stats.get_mut(&result[i - 1].0.project_id).unwrap();
-                    new_project_stats.completed_tasks += 1;
+                for id in [task.project_id, 0] {
+                    let new_project_stats = project_stats.get_mut(&id).unwrap();
+                    new_project_stats.total_time += new_task_stats.total_time;
+                    new_project_stats.total_worked += new_task_stats.total_worked;
+                    new_project_stats.pause_num += new_task_stats.pause_num;
+                    new_project_stats.longest_pause = max(
+                        new_project_stats.longest_pause,
+                        new_task_stats.longest_pause,
+                    );
+                    new_project_stats.longest_work =
+                        max(new_project_stats.longest_work, new_task_stats.longest_work);
+                    new_project_stats.total_tasks += 1;
+                    if completed {
+                        new_project_stats.completed_tasks += 1;
+                    }
                 }
 
                 task_stats.push(new_task_stats);
@@ -605,69 +767,214 @@ fn get_stats_map(
         }
     }
 
+    for task in task_stats.iter_mut() {
+        (task.remaining, task.overrun, task.percent) =
+            compare_with_plan(task.planned_time, task.total_worked);
+    }
+    for project in project_stats.values_mut() {
+        (project.remaining, project.overrun, project.percent) =
+            compare_with_plan(project.planned_time, project.total_worked);
+    }
+
     (project_stats, task_stats)
 }
 
-pub fn display_day_stats(args: &[String]) {
-    let mut date_to_seek = Local::now().naive_local().date();
-    let mut long_version = false;
-
-    let mut i = 0;
-    while i < args.len() {
-        match &args[i][..] {
-            "-d" | "-day" | "-ld" | "-dl" => {
-                if "-ld" == &args[i][..] || "-dl" == &args[i][..] {
-                    long_version = true;
-                }
-                i += 1;
-                if args.len() < 2 {
-                    println!("Too little arguments for this option!");
-                    return;
-                }
+/// A row of a report: the work done in a project on a day, or in the whole range when `date`
+/// is missing.
+#[derive(Serialize)]
+struct ReportRow {
+    date: Option<NaiveDate>,
+    project_id: i32,
+    project_name: String,
+    total_tasks: i32,
+    completed_tasks: i32,
+    #[serde(serialize_with = "as_seconds")]
+    total_worked: Duration,
+    pause_num: i32,
+}
 
-                let seeked_date = NaiveDate::parse_from_str(&args[1], "%Y-%m-%d");
-                if let Err(_) = seeked_date {
-                    println!("Couldn't parse the date format, giving results for current day");
-                }
+impl ReportRow {
+    fn new(date: Option<NaiveDate>, project: &ProjectStats) -> ReportRow {
+        ReportRow {
+            date,
+            project_id: project.project_id,
+            project_name: project.project_name.clone(),
+            total_tasks: project.total_tasks,
+            completed_tasks: project.completed_tasks,
+            total_worked: project.total_worked,
+            pause_num: project.pause_num,
+        }
+    }
+}
 
-                date_to_seek = seeked_date.unwrap_or(Local::now().naive_local().date());
-                i += 1;
-            }
-            "-l" => {
-                i += 1;
-                long_version = true;
-            }
+/// Returns the projects with any work in the stats, sorted by id, the "total" row last.
+fn worked_projects(project_stats: HashMap<i32, ProjectStats>) -> Vec<ProjectStats> {
+    let mut projects: Vec<ProjectStats> = project_stats
+        .into_values()
+        .filter(|project| project.total_tasks > 0)
+        .collect();
+    projects.sort_by_key(|project| (project.project_id == 0, project.project_id));
+    projects
+}
 
-            _ => {
-                println!("Unknown argument '{}': try again", &args[i]);
-                process::exit(-1);
+/// Displays the work done in every project on each day from `from` to `to`, followed by the
+/// totals per project for the whole range. With `live`, the running work and pauses are counted
+/// up to now.
+pub fn display_report(
+    from: NaiveDate,
+    to: NaiveDate,
+    live: bool,
+    format: Format,
+    connection: &mut DbConnection,
+) {
+    let until = live.then(workflow::config::now);
+    let stats = match db_operations::stats::get_stats_tasks(None, connection) {
+        Ok(x) => x,
+        Err(x) => return println!("{}", x),
+    };
+    let all_projects = get_projects(connection).ok();
+
+    let mut rows = vec![];
+    for day in from.iter_days().take_while(|day| *day <= to) {
+        let (project_stats, _) = get_stats_map(
+            all_projects.clone(),
+            Ok(stats.clone()),
+            Some(&day_range(day, day)),
+            until,
+        );
+        for project in worked_projects(project_stats) {
+            if project.project_id != 0 {
+                rows.push(ReportRow::new(Some(day), &project));
             }
         }
     }
+    let (project_stats, _) =
+        get_stats_map(all_projects, Ok(stats), Some(&day_range(from, to)), until);
+    rows.extend(
+        worked_projects(project_stats)
+            .iter()
+            .map(|project| ReportRow::new(None, project)),
+    );
+
+    if format != Format::Table {
+        rows.retain(|row| row.project_id != 0);
+        return output::print_rows(&rows, format);
+    }
+    if rows.is_empty() {
+        return println!("You didn't work on any projects from {} to {}", from, to);
+    }
+
+    println!("From {} to {} you worked on:", from, to);
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .apply_modifier(UTF8_ROUND_CORNERS)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec![
+            Cell::new("date")
+                .set_alignment(CellAlignment::Center)
+                .fg(Color::Cyan),
+            Cell::new("project_id")
+                .set_alignment(CellAlignment::Center)
+                .fg(Color::Cyan),
+            Cell::new("project_name")
+                .set_alignment(CellAlignment::Center)
+                .fg(Color::Cyan),
+            Cell::new("total tasks")
+                .set_alignment(CellAlignment::Center)
+                .fg(Color::Cyan),
+            Cell::new("completed tasks")
+                .set_alignment(CellAlignment::Center)
+                .fg(Color::Cyan),
+            Cell::new("total_worked")
+                .set_alignment(CellAlignment::Center)
+                .fg(Color::Cyan),
+            Cell::new("pause num")
+                .set_alignment(CellAlignment::Center)
+                .fg(Color::Cyan),
+        ]);
+    for row in rows {
+        table.add_row(vec![
+            Cell::new(row.date.map_or("total".to_string(), |x| x.to_string()))
+                .set_alignment(CellAlignment::Center),
+            Cell::new(if row.project_id == 0 {
+                "total".to_string()
+            } else {
+                row.project_id.to_string()
+            })
+            .set_alignment(CellAlignment::Center),
+            Cell::new(row.project_name).set_alignment(CellAlignment::Center),
+            Cell::new(row.total_tasks).set_alignment(CellAlignment::Center),
+            Cell::new(row.completed_tasks).set_alignment(CellAlignment::Center),
+            Cell::new(format_duration(row.total_worked)).set_alignment(CellAlignment::Center),
+            Cell::new(row.pause_num).set_alignment(CellAlignment::Center),
+        ]);
+    }
+    println!("{table}");
+}
 
-    let projects = get_date_projects(date_to_seek);
+pub fn display_day_stats(
+    date: Option<NaiveDate>,
+    long_version: bool,
+    live: bool,
+    format: Format,
+    connection: &mut DbConnection,
+) {
+    let date_to_seek = date.unwrap_or(workflow::config::now().date());
+    let until = live.then(workflow::config::now);
+    let range = day_range(date_to_seek, date_to_seek);
+    let range = Some(&range);
+
+    if format != Format::Table {
+        let stats = db_operations::stats::get_stats_tasks(None, connection);
+        let print_mode = if long_version {
+            PrintMode::Appearing
+        } else {
+            PrintMode::Project
+        };
+        display_range_content(stats, range, until, print_mode, None, format, connection);
+        return;
+    }
 
-    if let Ok(x) = projects {
-        let stats: Result<Vec<(Task, Option<i32>, Option<String>, Option<NaiveDateTime>)>, &str> =
-            db_operations::stats::get_day_stats_tasks(date_to_seek, None);
+    if let Ok(stats) = db_operations::stats::get_stats_tasks(None, connection) {
+        let all_projects = get_projects(connection).ok();
+        let (project_stats, _) = get_stats_map(all_projects, Ok(stats.clone()), range, until);
+        let x: Vec<ProjectStats> = worked_projects(project_stats)
+            .into_iter()
+            .filter(|project| project.project_id != 0)
+            .collect();
 
-        if x.len() > 0 {
-            if date_to_seek == Local::now().naive_local().date() {
+        if !x.is_empty() {
+            if date_to_seek == workflow::config::now().date() {
                 print!("Today ({})", date_to_seek);
             } else {
                 print!("On {} ", date_to_seek);
             }
             println!("you worked on the following {} projects:", x.len());
-            display_content(stats, PrintMode::Project, None);
+            display_range_content(
+                Ok(stats),
+                range,
+                until,
+                PrintMode::Project,
+                None,
+                format,
+                connection,
+            );
             if long_version {
                 println!("In details:");
                 for project in x {
                     println!("Project {}:", project.project_id);
-                    let stats = db_operations::stats::get_day_stats_tasks(
-                        date_to_seek,
-                        Some(project.project_id),
+                    let stats =
+                        db_operations::stats::get_stats_tasks(Some(project.project_id), connection);
+                    display_range_content(
+                        stats,
+                        range,
+                        until,
+                        PrintMode::Appearing,
+                        None,
+                        format,
+                        connection,
                     );
-                    display_content(stats, PrintMode::Appearing, None);
                 }
             }
         } else {
@@ -675,3 +982,40 @@ pub fn display_day_stats(args: &[String]) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::{Connection, SqliteConnection};
+
+    fn at(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn day_counts_work_closed_on_the_next_day() {
+        let connection =
+            &mut DbConnection::Sqlite(SqliteConnection::establish(":memory:").unwrap());
+        workflow::run_migrations(connection).unwrap();
+        let project = workflow::create_project(connection, "P1", None).unwrap();
+        let task = workflow::create_task(connection, project.project_id, "t1", None).unwrap();
+        workflow::create_log(connection, task.task_id, LogType::Begin, at(10, 23)).unwrap();
+        workflow::create_log(connection, task.task_id, LogType::Pause, at(11, 1)).unwrap();
+
+        let stats = db_operations::stats::get_stats_tasks(None, connection);
+        let all_projects = get_projects(connection).ok();
+        let worked = |from: u32, to: u32| {
+            let range = day_range(at(from, 0).date(), at(to, 0).date());
+            let (project_stats, _) =
+                get_stats_map(all_projects.clone(), stats.clone(), Some(&range), None);
+            project_stats[&project.project_id].total_worked
+        };
+        assert_eq!(worked(10, 10), Duration::hours(1));
+        assert_eq!(worked(11, 11), Duration::hours(1));
+        assert_eq!(worked(10, 11), Duration::hours(2));
+        assert_eq!(worked(12, 12), Duration::zero());
+    }
+}
@@ -0,0 +1,157 @@
+use chrono::{Duration, NaiveDateTime};
+use comfy_table::modifiers::UTF8_ROUND_CORNERS;
+use comfy_table::presets::UTF8_FULL;
+use comfy_table::*;
+use serde::Serialize;
+use workflow::intervals;
+use workflow::models::{Log, LogType, Task};
+use workflow::DbConnection;
+
+use crate::db_operations;
+use crate::output::{self, as_seconds, Format};
+use crate::stats;
+
+/// A task that has begun but not ended, with its current state.
+#[derive(Serialize)]
+struct TaskStatus {
+    task_id: i32,
+    project_id: i32,
+    task_name: String,
+    state: &'static str,
+    since: NaiveDateTime,
+    #[serde(serialize_with = "as_seconds")]
+    elapsed: Duration,
+    #[serde(serialize_with = "as_seconds")]
+    worked_today: Duration,
+}
+
+/// Returns the status of the task with its logs in chronological order, or `None` if it hasn't
+/// begun or has ended.
+fn task_status(task: &Task, logs: &[Log], now: NaiveDateTime) -> Option<TaskStatus> {
+    let recent_log = logs.last().filter(|x| x.log_type != LogType::End)?;
+
+    let history: Vec<(LogType, NaiveDateTime)> =
+        logs.iter().map(|log| (log.log_type, log.date)).collect();
+    let today = stats::day_range(now.date(), now.date());
+    let summary = intervals::summarize(&history, Some(&today), Some(now));
+
+    Some(TaskStatus {
+        task_id: task.task_id,
+        project_id: task.project_id,
+        task_name: task.task_name.clone(),
+        state: if recent_log.log_type == LogType::Pause {
+            "paused"
+        } else {
+            "working"
+        },
+        since: recent_log.date,
+        elapsed: now - recent_log.date,
+        worked_today: summary.total_worked,
+    })
+}
+
+/// Formats a duration as HOURS:MINUTES, short enough for a prompt.
+fn format_short(duration: Duration) -> String {
+    format!(
+        "{}:{:02}",
+        duration.num_hours(),
+        duration.num_minutes() % 60
+    )
+}
+
+/// Displays the tasks that have begun but not ended: whether they are worked on or paused, for
+/// how long and how much they were worked on today. With `short`, prints them in a single line,
+/// e.g. for a shell prompt or a status bar.
+pub fn display_status(short: bool, format: Format, connection: &mut DbConnection) {
+    let rows = match db_operations::logs::get_user_task_logs(connection) {
+        Ok(x) => x,
+        Err(_) => return println!("An error occured while fetching logs"),
+    };
+
+    let now = workflow::config::now();
+    let statuses: Vec<TaskStatus> = rows
+        .chunk_by(|a, b| a.0.task_id == b.0.task_id)
+        .filter_map(|rows| {
+            let logs: Vec<Log> = rows.iter().map(|(_, log)| log.clone()).collect();
+            task_status(&rows[0].0, &logs, now)
+        })
+        .collect();
+
+    if short {
+        let line: Vec<String> = statuses
+            .iter()
+            .map(|x| {
+                format!(
+                    "{} {} {} (today {})",
+                    x.task_name,
+                    x.state,
+                    format_short(x.elapsed),
+                    format_short(x.worked_today)
+                )
+            })
+            .collect();
+        return println!(
+            "{}",
+            if line.is_empty() {
+                "idle".to_string()
+            } else {
+                line.join(" | ")
+            }
+        );
+    }
+    if format != Format::Table {
+        return output::print_rows(&statuses, format);
+    }
+    if statuses.is_empty() {
+        return println!("No tasks in progress");
+    }
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .apply_modifier(UTF8_ROUND_CORNERS)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec![
+            Cell::new("task_id")
+                .set_alignment(CellAlignment::Center)
+                .fg(Color::Cyan),
+            Cell::new("project_id")
+                .set_alignment(CellAlignment::Center)
+                .fg(Color::Cyan),
+            Cell::new("task_name")
+                .set_alignment(CellAlignment::Center)
+                .fg(Color::Cyan),
+            Cell::new("state")
+                .set_alignment(CellAlignment::Center)
+                .fg(Color::Cyan),
+            Cell::new("since")
+                .set_alignment(CellAlignment::Center)
+                .fg(Color::Cyan),
+            Cell::new("elapsed")
+                .set_alignment(CellAlignment::Center)
+                .fg(Color::Cyan),
+            Cell::new("worked today")
+                .set_alignment(CellAlignment::Center)
+                .fg(Color::Cyan),
+        ]);
+    for status in statuses {
+        table.add_row(vec![
+            Cell::new(status.task_id).set_alignment(CellAlignment::Center),
+            Cell::new(status.project_id).set_alignment(CellAlignment::Center),
+            Cell::new(status.task_name).set_alignment(CellAlignment::Center),
+            Cell::new(status.state)
+                .set_alignment(CellAlignment::Center)
+                .fg(if status.state == "working" {
+                    Color::Green
+                } else {
+                    Color::Yellow
+                }),
+            Cell::new(status.since.format(&workflow::config::get().date_format))
+                .set_alignment(CellAlignment::Center),
+            Cell::new(stats::format_duration(status.elapsed)).set_alignment(CellAlignment::Center),
+            Cell::new(stats::format_duration(status.worked_today))
+                .set_alignment(CellAlignment::Center),
+        ]);
+    }
+    println!("{table}");
+}
@@ -1,73 +1,41 @@
 use comfy_table::modifiers::UTF8_ROUND_CORNERS;
 use comfy_table::presets::UTF8_FULL;
 use comfy_table::*;
-use regex::Regex;
-use std::process;
+use workflow::lookup::{self, Match};
+use workflow::models::{Task, TaskChanges};
+use workflow::DbConnection;
 
-use crate::db_operations;
+use crate::output::{self, Format};
+use crate::{db_operations, projects, stats};
 
-pub fn add_task(args: Vec<String>) {
-    if args.len() < 4 {
-        eprintln!("Too few args");
-        process::exit(-1);
-    }
-    let time_regex = Regex::new(r"^\d+:\d+:\d+$").unwrap();
-    let arg_regex: Regex = Regex::new(r"-.*").unwrap();
-    let project_id_regex = Regex::new(r"^\d+").unwrap();
-
-    if arg_regex.is_match(&args[2]) {
-        println!("First argument must be task name!");
-        process::exit(-1);
-    }
-
-    if !project_id_regex.is_match(&args[3]) {
-        println!("Second argument must be project id!");
-        process::exit(-1);
-    }
-    let task_name = &args[2];
-    println!("Task name: {}", task_name);
-
-    let task_project_id = &args[3].parse::<i32>();
-    let task_project_id = match task_project_id {
-        Err(_) => {
-            println!("Wrong project id format, couldn't parse");
-            process::exit(-1);
-        }
-        Ok(x) => x,
+pub fn add_task(
+    task_name: &str,
+    project: &str,
+    planned_minutes: Option<i32>,
+    connection: &mut DbConnection,
+) {
+    let Some(project) = projects::resolve_project(project, connection) else {
+        return;
     };
+    println!("Task name: {}", task_name);
 
-    let mut time_planned: Option<&str> = None;
-
-    let mut i = 4;
-    while i < args.len() {
-        println!("{}, {}", arg_regex.is_match(&args[i]), &args[i]);
-        match &args[i][..] {
-            "-t" => {
-                i += 1;
-                if time_regex.is_match(&args[i]) {
-                    time_planned = Some(&args[i]);
-                    println!("{:?}", time_planned);
-                } else {
-                    println!("Wrong time format!");
-                }
-                i += 1;
-            }
-            _ => {
-                println!("Unknown argument '{}': try again", &args[i]);
-                process::exit(-1);
-            }
-        }
-    }
-
-    if let Err(x) =
-        db_operations::tasks::add_task(task_project_id.to_owned(), task_name, time_planned, true)
-    {
+    if let Err(x) = db_operations::tasks::add_task(
+        project.project_id,
+        task_name,
+        planned_minutes,
+        true,
+        connection,
+    ) {
         println!("{}", x);
     }
 }
-pub fn display_tasks() {
-    let a = db_operations::tasks::get_tasks();
+pub fn display_tasks(include_archived: bool, format: Format, connection: &mut DbConnection) {
+    let a = db_operations::tasks::get_tasks(include_archived, connection);
     if let Ok(x) = a {
+        if format != Format::Table {
+            return output::print_rows(&x, format);
+        }
+
         let mut table = Table::new();
         table
             .load_preset(UTF8_FULL)
@@ -94,12 +62,145 @@ pub fn display_tasks() {
             table.add_row(vec![
                 Cell::new(row.task_id).set_alignment(CellAlignment::Center),
                 Cell::new(row.project_id).set_alignment(CellAlignment::Center),
-                Cell::new(row.task_name).set_alignment(CellAlignment::Center),
+                Cell::new(if row.archived {
+                    format!("{} (archived)", row.task_name)
+                } else {
+                    row.task_name
+                })
+                .set_alignment(CellAlignment::Center),
                 Cell::new(row.username).set_alignment(CellAlignment::Center),
-                Cell::new(row.planned_time.unwrap_or("null".to_string()))
+                Cell::new(stats::format_minutes(row.planned_minutes))
                     .set_alignment(CellAlignment::Center),
             ]);
         }
         println!("{table}");
     }
 }
+
+/// Finds the task given by id, by name or as PROJECT/TASK, printing a message when there is none
+/// or the name is ambiguous.
+pub fn resolve_task(arg: &str, connection: &mut DbConnection) -> Option<Task> {
+    find_task(arg, connection)
+        .map_err(|x| println!("{}", x))
+        .ok()
+}
+
+/// Like `resolve_task`, but returns the message instead of printing it.
+pub fn find_task(arg: &str, connection: &mut DbConnection) -> Result<Task, String> {
+    if let Ok(num) = arg.parse::<i32>() {
+        return match db_operations::tasks::find_task_by_id(&num, connection) {
+            Ok(Some(x)) => Ok(x),
+            Ok(None) => Err("No such task!".to_string()),
+            Err(x) => Err(x.to_string()),
+        };
+    }
+
+    let mut candidates = db_operations::tasks::get_tasks_with_project_names(connection)?;
+    let mut name = arg;
+    // A name with a slash is taken as PROJECT/TASK, unless it's the whole name of a task
+    if let Some((project, task)) = arg.split_once('/') {
+        if !candidates
+            .iter()
+            .any(|(x, _)| x.task_name.to_lowercase() == arg.to_lowercase())
+        {
+            let project = projects::find_project(project, connection)?;
+            candidates.retain(|(x, _)| x.project_id == project.project_id);
+            name = task;
+        }
+    }
+
+    match lookup::find(name, candidates, |(task, _)| task.task_name.as_str()) {
+        Match::One((x, _)) => Ok(x),
+        Match::Ambiguous(x) => Err(format!(
+            "Task \"{}\" is ambiguous, it may be:\n{}\nGive the task as PROJECT/TASK or by id",
+            arg,
+            candidates_list(&x)
+        )),
+        Match::NoMatch(x) if x.is_empty() => Err("No such task!".to_string()),
+        Match::NoMatch(x) => Err(format!("No such task!\nDid you mean:\n{}", candidates_list(&x))),
+    }
+}
+
+fn candidates_list(tasks: &[(Task, String)]) -> String {
+    tasks
+        .iter()
+        .map(|(task, project_name)| {
+            format!("  {:>4}  {}/{}", task.task_id, project_name, task.task_name)
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub fn edit_task(
+    task: &str,
+    task_name: Option<&str>,
+    planned_minutes: Option<i32>,
+    connection: &mut DbConnection,
+) {
+    if task_name.is_none() && planned_minutes.is_none() {
+        println!("Nothing to change, use -n NAME or -t TIME");
+        return;
+    }
+
+    let Some(task) = resolve_task(task, connection) else {
+        return;
+    };
+    let changes = TaskChanges {
+        task_name,
+        planned_minutes,
+        ..Default::default()
+    };
+    match db_operations::tasks::edit_task(task.task_id, &changes, connection) {
+        Ok(x) => println!("Saved task {} \"{}\"", x.task_id, x.task_name),
+        Err(x) => println!("{}", x),
+    }
+}
+
+pub fn move_task(task: &str, project: &str, connection: &mut DbConnection) {
+    let Some(task) = resolve_task(task, connection) else {
+        return;
+    };
+    let Some(project) = projects::resolve_project(project, connection) else {
+        return;
+    };
+
+    let changes = TaskChanges {
+        project_id: Some(project.project_id),
+        ..Default::default()
+    };
+    match db_operations::tasks::edit_task(task.task_id, &changes, connection) {
+        Ok(x) => println!(
+            "Moved task \"{}\" to project \"{}\"",
+            x.task_name, project.project_name
+        ),
+        Err(x) => println!("{}", x),
+    }
+}
+
+pub fn archive_task(task: &str, archived: bool, connection: &mut DbConnection) {
+    let Some(task) = resolve_task(task, connection) else {
+        return;
+    };
+    let changes = TaskChanges {
+        archived: Some(archived),
+        ..Default::default()
+    };
+    match db_operations::tasks::edit_task(task.task_id, &changes, connection) {
+        Ok(x) if archived => println!("Archived task \"{}\"", x.task_name),
+        Ok(x) => println!("Restored task \"{}\"", x.task_name),
+        Err(x) => println!("{}", x),
+    }
+}
+
+pub fn delete_task(task: &str, cascade: bool, connection: &mut DbConnection) {
+    let Some(task) = resolve_task(task, connection) else {
+        return;
+    };
+    match db_operations::tasks::delete_task(task.task_id, cascade, connection) {
+        Ok(logs_num) => println!(
+            "Deleted task \"{}\" and {} of its logs",
+            task.task_name, logs_num
+        ),
+        Err(x) => println!("{}", x),
+    }
+}